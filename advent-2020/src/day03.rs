@@ -9,49 +9,35 @@ TODO:
 - Learn, once again, what is str *really*?
  */
 
-pub fn day03_1_function(input: &str, slope: Slope) -> usize {
-    let tree = '#';
-    find_map_spots_visited(transform_to_2d(input), slope.right, slope.down)
-        .iter()
-        // Double reference https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.filter
-        .filter(|&&spot| spot == tree)
-        .count()
-}
+mod forest;
 
-pub fn day03_2_function(input: &str, slopes: Vec<Slope>) -> usize {
-    let mut multi = 1;
-    for slope in slopes {
-        multi *= day03_1_function(input, slope)
-    }
-    multi
-}
+pub use forest::{Forest, RaggedRow};
 
-fn transform_to_2d(input: &str) -> Vec<Vec<char>> {
-    let vec: Vec<&str> = input.split("\n").collect();
-    let mut return_vec: Vec<Vec<char>> = Vec::new();
-    for v in vec {
-        return_vec.push(v.trim().chars().collect())
-    }
+pub fn day03_1_function(input: &str, slope: Slope) -> Result<usize, RaggedRow> {
+    Ok(Forest::parse(input)?.trees(&slope))
+}
 
-    return_vec
+pub fn day03_2_function(input: &str, slopes: Vec<Slope>) -> Result<usize, RaggedRow> {
+    let forest = Forest::parse(input)?;
+    Ok(forest.trees_for(&slopes).into_iter().product())
 }
 
-fn find_map_spots_visited(ndarray: Vec<Vec<char>>, right: usize, down: usize) -> Vec<char> {
-    if ndarray.is_empty() {
-        return Vec::new();
-    }
-    // Subtract 1 because starts in the top left corner
-    let max_down_visits = (ndarray.len() - 1) / down;
-    let mut map_spots: Vec<char> = Vec::new();
+/// The map with the path for `slope` drawn on it.
+pub fn day03_path(input: &str, slope: Slope) -> Result<String, RaggedRow> {
+    let forest = Forest::parse(input)?;
+    Ok(forest.render(&forest.path(&slope)))
+}
 
-    for i in 0..max_down_visits {
-        let map_spot_down = (i + 1) * down;
-        let map_spot_right = (right * (i + 1)) % ndarray[0].len();
-        map_spots.push(ndarray[map_spot_down][map_spot_right]);
-    }
-    map_spots
+/// The slope with the fewest trees, going at most `max_right` right and `max_down` down per step.
+pub fn day03_best_slope(
+    input: &str,
+    max_right: usize,
+    max_down: usize,
+) -> Result<Option<(Slope, usize)>, RaggedRow> {
+    Ok(Forest::parse(input)?.fewest_trees(max_right, max_down))
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Slope {
     pub right: usize,
     pub down: usize,
@@ -85,12 +71,24 @@ mod tests {
 
     #[test]
     fn it_should_count_the_number_of_trees_encountered() {
-        let result = day03_1_function(SNIPPET, Slope { right: 3, down: 1 });
+        let result = day03_1_function(SNIPPET, Slope { right: 3, down: 1 }).unwrap();
         assert_eq!(result, 7);
-        assert_eq!(day03_1_function(SNIPPET, Slope { right: 1, down: 1 }), 2);
-        assert_eq!(day03_1_function(SNIPPET, Slope { right: 5, down: 1 }), 3);
-        assert_eq!(day03_1_function(SNIPPET, Slope { right: 7, down: 1 }), 4);
-        assert_eq!(day03_1_function(SNIPPET, Slope { right: 1, down: 2 }), 2);
+        assert_eq!(
+            day03_1_function(SNIPPET, Slope { right: 1, down: 1 }),
+            Ok(2)
+        );
+        assert_eq!(
+            day03_1_function(SNIPPET, Slope { right: 5, down: 1 }),
+            Ok(3)
+        );
+        assert_eq!(
+            day03_1_function(SNIPPET, Slope { right: 7, down: 1 }),
+            Ok(4)
+        );
+        assert_eq!(
+            day03_1_function(SNIPPET, Slope { right: 1, down: 2 }),
+            Ok(2)
+        );
     }
 
     #[test]
@@ -98,15 +96,14 @@ mod tests {
         let snippet = "..##.......
         #...#...#..
         .#....#..#.";
-        let result = transform_to_2d(snippet);
-        let expected: Vec<Vec<char>> = vec![
-            vec!['.', '.', '#', '#', '.', '.', '.', '.', '.', '.', '.'],
-            vec!['#', '.', '.', '.', '#', '.', '.', '.', '#', '.', '.'],
-            vec!['.', '#', '.', '.', '.', '.', '#', '.', '.', '#', '.'],
-        ];
-        assert_eq!(result, expected);
-        assert_eq!(result[0][0], '.');
-        assert_eq!(result.len(), 3);
+        let result = Forest::parse(snippet).unwrap();
+        assert_eq!(result.width(), 11);
+        assert_eq!(result.height(), 3);
+        assert!(!result.is_tree(0, 0));
+        assert!(result.is_tree(0, 2));
+        assert!(result.is_tree(1, 0));
+        // and it goes on forever to the right
+        assert!(result.is_tree(1, 11));
     }
 
     #[test]
@@ -114,15 +111,25 @@ mod tests {
         let snippet = "..##.......
         #...#...#..
         .#....#..#.";
-        let array2d = transform_to_2d(snippet);
+        let forest = Forest::parse(snippet).unwrap();
         let slope = Slope { right: 3, down: 1 };
-        let result = find_map_spots_visited(array2d, slope.right, slope.down);
+        let result: Vec<char> = forest
+            .path(&slope)
+            .into_iter()
+            .map(|(row, column)| {
+                if forest.is_tree(row, column) {
+                    '#'
+                } else {
+                    '.'
+                }
+            })
+            .collect();
         let expected: Vec<char> = vec!['.', '#'];
         assert_eq!(result, expected)
     }
 
     #[test]
     fn it_should_multiply_the_number_of_trees_encountered_for_all_given_slopes() {
-        assert_eq!(day03_2_function(SNIPPET, get_slopes()), 336);
+        assert_eq!(day03_2_function(SNIPPET, get_slopes()), Ok(336));
     }
 }
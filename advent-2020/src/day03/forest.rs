@@ -0,0 +1,236 @@
+/*
+The map, parsed once. Every slope walks the same Forest instead of rebuilding the grid, and the
+walk hands back coordinates, so the same path can be counted, compared or drawn.
+*/
+use super::Slope;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forest {
+    width: usize,
+    /// true is a tree
+    rows: Vec<Vec<bool>>,
+}
+
+/// A row that isn't as wide as the first one, so the pattern can't repeat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaggedRow {
+    pub line: usize,
+    pub width: usize,
+    pub expected: usize,
+}
+
+impl Display for RaggedRow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}: row is {} wide, expected {}",
+            self.line, self.width, self.expected
+        )
+    }
+}
+
+impl Error for RaggedRow {}
+
+impl Forest {
+    /// One row per line, `#` is a tree and anything else is open. Blank lines are skipped.
+    /// Every row has to be as wide as the first.
+    pub fn parse(input: &str) -> Result<Forest, RaggedRow> {
+        let mut width = None;
+        let mut rows = Vec::new();
+        for (idx, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let row: Vec<bool> = line.chars().map(|c| c == '#').collect();
+            let expected = *width.get_or_insert(row.len());
+            if row.len() != expected {
+                return Err(RaggedRow {
+                    line: idx + 1,
+                    width: row.len(),
+                    expected,
+                });
+            }
+            rows.push(row);
+        }
+        Ok(Forest {
+            width: width.unwrap_or(0),
+            rows,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The pattern repeats to the right forever, so `column` wraps around.
+    pub fn is_tree(&self, row: usize, column: usize) -> bool {
+        self.rows[row][column % self.width]
+    }
+
+    /// The (row, column) of every spot the toboggan lands on, not counting the top left corner it
+    /// starts from. Columns are already wrapped into the map.
+    pub fn path(&self, slope: &Slope) -> Vec<(usize, usize)> {
+        if self.rows.is_empty() || slope.down == 0 {
+            return Vec::new();
+        }
+        // wrap `right` first so the column never grows past twice the width
+        let right = slope.right % self.width();
+        (slope.down..self.height())
+            .step_by(slope.down)
+            .scan(0, |column, row| {
+                *column = (*column + right) % self.width();
+                Some((row, *column))
+            })
+            .collect()
+    }
+
+    pub fn trees(&self, slope: &Slope) -> usize {
+        self.path(slope)
+            .into_iter()
+            .filter(|&(row, column)| self.is_tree(row, column))
+            .count()
+    }
+
+    /// Tree counts for each slope, in the same order.
+    pub fn trees_for(&self, slopes: &[Slope]) -> Vec<usize> {
+        slopes.iter().map(|slope| self.trees(slope)).collect()
+    }
+
+    /// Tries every slope going right 1 to `max_right` and down 1 to `max_down`. Going straight
+    /// down isn't a slope. Ties go to the slope found first, smallest `down` then smallest `right`.
+    /// Going right by more than the width lands on the same columns as some smaller slope, and
+    /// going down by more than the height lands nowhere, so those aren't tried.
+    pub fn fewest_trees(&self, max_right: usize, max_down: usize) -> Option<(Slope, usize)> {
+        let max_right = max_right.min(self.width().max(1));
+        let max_down = max_down.min(self.height().max(1));
+        (1..=max_down)
+            .flat_map(|down| (1..=max_right).map(move |right| Slope { right, down }))
+            .map(|slope| {
+                let trees = self.trees(&slope);
+                (slope, trees)
+            })
+            .min_by_key(|&(_, trees)| trees)
+    }
+
+    /// The map with the path drawn on it, `O` where it lands on open ground and `X` on a tree.
+    pub fn render(&self, path: &[(usize, usize)]) -> String {
+        let mut grid: Vec<Vec<char>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&tree| if tree { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        for &(row, column) in path {
+            grid[row][column] = if self.is_tree(row, column) { 'X' } else { 'O' };
+        }
+        grid.into_iter()
+            .map(|row| row.into_iter().chain(Some('\n')).collect::<String>())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNIPPET: &str = "..##.......
+        #...#...#..
+        .#....#..#.
+        ..#.#...#.#
+        .#...##..#.
+        ..#.##.....
+        .#.#.#....#
+        .#........#
+        #.##...#...
+        #...##....#
+        .#..#...#.#";
+
+    #[test]
+    fn it_should_walk_the_path() {
+        let forest = Forest::parse(SNIPPET).unwrap();
+        assert_eq!((forest.width(), forest.height()), (11, 11));
+        let path = forest.path(&Slope { right: 1, down: 2 });
+        assert_eq!(path, vec![(2, 1), (4, 2), (6, 3), (8, 4), (10, 5)]);
+        assert_eq!(forest.path(&Slope { right: 3, down: 1 })[3], (4, 1));
+        assert!(forest.path(&Slope { right: 1, down: 0 }).is_empty());
+        // the same columns as going right 3
+        let far = forest.path(&Slope {
+            right: usize::MAX / 11 * 11 + 3,
+            down: 1,
+        });
+        assert_eq!(far, forest.path(&Slope { right: 3, down: 1 }));
+        assert!(forest
+            .path(&Slope {
+                right: 1,
+                down: usize::MAX
+            })
+            .is_empty());
+    }
+
+    #[test]
+    fn it_should_count_trees_for_any_slopes() {
+        let forest = Forest::parse(SNIPPET).unwrap();
+        let slopes = [
+            Slope { right: 3, down: 1 },
+            Slope { right: 2, down: 3 },
+            Slope { right: 11, down: 1 },
+        ];
+        // right 11 is straight down the first column
+        assert_eq!(forest.trees_for(&slopes), vec![7, 1, 3]);
+    }
+
+    #[test]
+    fn it_should_find_the_slope_with_fewest_trees() {
+        let forest = Forest::parse(SNIPPET).unwrap();
+        assert_eq!(
+            forest.fewest_trees(3, 2),
+            Some((Slope { right: 2, down: 1 }, 1))
+        );
+        assert_eq!(forest.fewest_trees(3, 0), None);
+        assert_eq!(forest.fewest_trees(0, 3), None);
+        assert_eq!(
+            forest.fewest_trees(usize::MAX, usize::MAX),
+            Some((Slope { right: 5, down: 2 }, 0))
+        );
+        // straight down the first column would miss every tree
+        let forest = Forest::parse("..\n.#\n.#").unwrap();
+        assert_eq!(
+            forest.fewest_trees(1, 2),
+            Some((Slope { right: 1, down: 1 }, 1))
+        );
+    }
+
+    #[test]
+    fn it_should_draw_the_path_over_the_map() {
+        let forest = Forest::parse("..##...\n#...#..\n.#....#").unwrap();
+        let path = forest.path(&Slope { right: 3, down: 1 });
+        assert_eq!(forest.render(&path), "..##...\n#..O#..\n.#....X\n");
+    }
+
+    #[test]
+    fn it_should_reject_ragged_rows() {
+        assert_eq!(
+            Forest::parse("..##\n\n#..\n.#.."),
+            Err(RaggedRow {
+                line: 3,
+                width: 3,
+                expected: 4
+            })
+        );
+        assert_eq!(
+            Forest::parse("..##\n#...#").unwrap_err().to_string(),
+            "line 2: row is 5 wide, expected 4"
+        );
+        assert_eq!(Forest::parse("").map(|forest| forest.height()), Ok(0));
+    }
+}
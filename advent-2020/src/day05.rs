@@ -11,7 +11,13 @@
  I am also very new to Rust and still a baby software engineer. When I get my mic, I can talk more about that :)
  So the code so far... I have some tests, and some implementations
  */
-use indextree::{Arena, NodeId};
+mod bsp;
+mod seat_map;
+#[cfg(test)]
+mod tree;
+
+use bsp::{BspError, Plane};
+use seat_map::SeatMap;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
@@ -23,25 +29,11 @@ struct BoardingPass {
     seat_id: u32,
 }
 
-impl BoardingPass {
-    const ALLOWED_FIRST_CHARS: [char; 2] = ['B', 'F'];
-    const ALLOWED_SECOND_CHARS: [char; 2] = ['L', 'R'];
-
-    fn is_valid_length(s: &str) -> bool {
-        s.len() == 10
-    }
-
-    fn is_valid_chars(s: &str) -> bool {
-        let valid_first_chars = s[0..7] // the first 7 characters
-            .chars()
-            .all(|c| Self::ALLOWED_FIRST_CHARS.contains(&c));
-        let valid_second_chars = s[7..] // the rest
-            .chars()
-            .all(|c| Self::ALLOWED_SECOND_CHARS.contains(&c));
-        valid_first_chars && valid_second_chars
-    }
+impl FromStr for BoardingPass {
+    type Err = ParseBoardingPassError;
 
-    fn find_the_row(s: &str) -> u32 {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let plane = Plane::default();
         /*
         input: FBFBBFFRLR
         Rows are 0 - 127, 2^7
@@ -63,67 +55,21 @@ impl BoardingPass {
          We have a smaller example to work with (from 128 rows, to 8 rows). We think trees are still a good idea.
          Next we can write some simple tests that test this smaller tree. We also need to learn how to write a tree.
          Sneak peeking at some Rust docs.... then done with stream. Thanks for watching!
-         */
-        let tree_array = Self::generate_boarding_pass_tree(8);
-        let mut node = Node::new(tree_array, s);
-        node.traverse_tree_array();
-        node.get_current_value() - node.offset
-    }
-
-    fn find_the_column(s: &str) -> u32 {
-        let tree_array = Self::generate_boarding_pass_tree(4);
-        let mut node = Node::new(tree_array, s);
-        node.traverse_tree_array();
-        node.get_current_value() - node.offset // faked it and it passed; now we need to remove duplication
-    }
-
-    /// Generates a tree using a vector, breadth-first representation
-    /// Wiki: https://en.wikipedia.org/wiki/Binary_tree#Arrays
-    /// Levels here is referring to the depth of a perfect binary tree (2 children all internal nodes, and leaf nodes are at the same level).
-    /// https://towardsdatascience.com/5-types-of-binary-tree-with-cool-illustrations-9b335c430254
-    /// For example:
-    ///                       R
-    ///                   F      B
-    ///               F   B    F  B
-    ///              F B  F B  F B F B
-    /// This tree's levels = 4.
-    fn generate_boarding_pass_tree(levels: u32) -> TreeArray {
-        let base: u32 = 2;
-        let max_nodes = base.pow(levels) - 1;
-        let terminal_nodes = base.pow(levels - 1);
-        // index_value_offset is the left most value of lowest value, it's also the offset to get the numerical value of the row
-        let index_value_offset = max_nodes - terminal_nodes;
-
-        let tree_array: Vec<u32> = (0..max_nodes).collect();
-        TreeArray {
-            array: tree_array,
-            max_nodes,
-            terminal_nodes,
-            index_value_offset,
-        }
-    }
-}
-
-impl FromStr for BoardingPass {
-    type Err = ParseBoardingPassError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if !Self::is_valid_length(s) {
-            return Err(ParseBoardingPassError {
-                message: format!("invalid length. expected 10, found: {}", s.len()),
-            });
-        }
-
-        if !Self::is_valid_chars(s) {
-            return Err(ParseBoardingPassError {
-                message: format!("invalid chars found for: {}", s),
-            });
-        }
 
+         ... a while later: the tree was binary counting all along, F is a 0 bit and B is a 1 bit.
+         So now the decoding lives in bsp.rs.
+         */
+        let error = |e: BspError| ParseBoardingPassError {
+            message: match e {
+                BspError::InvalidChar { .. } => format!("{} in: {}", e, s),
+                _ => e.to_string(),
+            },
+        };
+        let (row, column) = plane.decode(s).map_err(error)?;
         Ok(BoardingPass {
-            row: Self::find_the_row(&s[..7]),
-            column: Self::find_the_column(&s[7..]),
-            seat_id: (Self::find_the_row(&s[..7]) * 8) + Self::find_the_column(&s[7..]),
+            row,
+            column,
+            seat_id: plane.seat_id(row, column).map_err(error)?,
         })
     }
 }
@@ -135,112 +81,34 @@ struct ParseBoardingPassError {
 
 impl Display for ParseBoardingPassError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "error parsing boarding pass")
+        write!(f, "error parsing boarding pass: {}", self.message)
     }
 }
 
-impl Error for ParseBoardingPassError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        Some(self)
-    }
-}
+impl Error for ParseBoardingPassError {}
 
 pub(crate) fn day05_1(input: &str) -> u32 {
     input
         .split("\r\n")
         .map(BoardingPass::from_str)
-        .filter(|bp| bp.is_ok())
-        .map(|bp_ok| bp_ok.unwrap())
+        .filter_map(Result::ok)
         .max_by_key(|good_bp| good_bp.seat_id)
         .map_or(0, |final_bp| final_bp.seat_id)
 }
 
 pub(crate) fn day05_2(input: &str) -> u32 {
-    let mut all_seats: Vec<BoardingPass> = input
-        .split("\r\n")
-        .map(BoardingPass::from_str)
-        .filter(|bp| bp.is_ok())
-        .map(|bp_ok| bp_ok.unwrap())
-        .collect();
-    all_seats.sort_by_key(|bp| bp.seat_id);
-    let min_seat_id = all_seats.get(0).unwrap().seat_id;
-    let max_seat_id = all_seats.get(all_seats.len() - 1).unwrap().seat_id;
-    let all_seat_ids: Vec<u32> = all_seats.iter().map(|bp| bp.seat_id).collect();
-    for val in min_seat_id..max_seat_id {
-        if !all_seat_ids.contains(&val) {
-            return val;
-        }
-    }
-    0
+    SeatMap::from_input(input).free_seat().unwrap_or(0)
 }
 
-struct TreeArray {
-    array: Vec<u32>,
-    max_nodes: u32,
-    terminal_nodes: u32,
-    index_value_offset: u32,
-}
-struct Node {
-    tree_array: Vec<u32>,
-    boarding_pass: String,
-    current: Option<u32>,
-    offset: u32,
-}
-
-impl Node {
-    fn new(tree_array: TreeArray, boarding_pass: &str) -> Node {
-        Node {
-            tree_array: tree_array.array,
-            boarding_pass: boarding_pass.to_owned(),
-            current: Some(0),
-            offset: tree_array.index_value_offset,
-        }
-    }
-
-    fn traverse_tree_array(&mut self) {
-        let mut error = false;
-        for c in self.boarding_pass.chars() {
-            if c == 'F' || c == 'L' {
-                self.current = match self.current {
-                    Some(num) => Some((2 * num) + 1),
-                    None => {
-                        error = true;
-                        break;
-                    }
-                }
-            } else {
-                self.current = match self.current {
-                    Some(num) => Some((2 * num) + 2),
-                    None => {
-                        error = true;
-                        break;
-                    }
-                }
-            }
-        }
-        if error {
-            println!("Something bad is happening in Oz");
-        }
-    }
-
-    fn get_current_value(&self) -> u32 {
-        match self.current {
-            Some(num) => num,
-            None => 0,
-        }
-    }
-
-    // fn is_legit_row(&self, tree_array: &TreeArray) -> bool {
-    //     match self.current {
-    //         Some(num) => tree_array.array.clone().len() > u32::try_usize(num).unwrap(),
-    //         None => false,
-    //     }
-    // }
+/// Everything we know about the plane, drawn out. Run with `cargo run -- day05 seats`.
+pub(crate) fn day05_seat_map(input: &str) -> String {
+    SeatMap::from_input(input).to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use indextree::Arena;
 
     // This would basically be the final solution -- If I can write code that does this reliably, then
     // I can feel confident that I have solved the puzzle (see? This is why tests are so cool!)
@@ -269,14 +137,14 @@ mod tests {
     #[test]
     fn it_should_find_the_right_row() {
         let snippet = "FBFBBFFRLR";
-        assert_eq!(BoardingPass::find_the_row(&snippet[..7]), 44); // We deliberately write a test that fails but has what we want
+        assert_eq!(BoardingPass::from_str(snippet).map(|bp| bp.row), Ok(44)); // We deliberately write a test that fails but has what we want
     }
 
     // Then what if I write code that just finds the right column?
     #[test]
     fn it_should_find_the_right_column() {
         let snippet = "FBFBBFFRLR";
-        assert_eq!(BoardingPass::find_the_column(&snippet[7..]), 5);
+        assert_eq!(BoardingPass::from_str(snippet).map(|bp| bp.column), Ok(5));
     }
 
     /*
@@ -301,17 +169,43 @@ mod tests {
         let other_bad_snippet = "FBFBBFFRZR";
 
         let expected_1 = Err(ParseBoardingPassError {
-            message: format!("invalid chars found for: {}", snippet),
+            message: format!(
+                "invalid char 'A' at position 3 (expected F or B) in: {}",
+                snippet
+            ),
         });
         let expected_2 = Err(ParseBoardingPassError {
-            message: format!("invalid chars found for: {}", other_bad_snippet),
+            message: format!(
+                "invalid char 'Z' at position 8 (expected L or R) in: {}",
+                other_bad_snippet
+            ),
         });
 
-        BoardingPass::from_str(other_bad_snippet).map_err(|z| println!("{:?}", z.source()));
+        let _ = BoardingPass::from_str(other_bad_snippet).map_err(|z| println!("{:?}", z.source()));
         assert_eq!(BoardingPass::from_str(snippet), expected_1);
         assert_eq!(BoardingPass::from_str(other_bad_snippet), expected_2);
     }
 
+    #[test]
+    fn it_should_not_panic_on_multi_byte_characters() {
+        let snippet = "FBFéBFFRLR";
+        assert_eq!(
+            BoardingPass::from_str(snippet),
+            Err(ParseBoardingPassError {
+                message: format!(
+                    "invalid char 'é' at position 3 (expected F or B) in: {}",
+                    snippet
+                ),
+            })
+        );
+        assert_eq!(
+            BoardingPass::from_str("FBFBBFFRLR!")
+                .unwrap_err()
+                .to_string(),
+            "error parsing boarding pass: invalid length. expected 10, found: 11"
+        );
+    }
+
     #[test]
     fn i_play_with_arena() {
         let arena = &mut Arena::new();
@@ -361,39 +255,14 @@ mod tests {
         root.append(child, arena);
 
         assert!(arena.get(root).is_some());
-        arena.get(root).map(|n| assert!(n.parent().is_none()));
+        if let Some(n) = arena.get(root) {
+            assert!(n.parent().is_none())
+        }
 
         assert!(arena.get(child).is_some());
-        arena.get(child).map(|n| assert_eq!(n.parent(), Some(root)));
-    }
-
-    #[test]
-    fn create_binary_tree_at_1_level() {
-        let tree_array = BoardingPass::generate_boarding_pass_tree(1);
-        // let actual = root.descendants(arena).collect::<Vec<NodeId>>().len();
-        let actual = tree_array.array.len();
-        assert_eq!(actual, 1); // creates 1 nodes
-    }
-
-    #[test]
-    fn create_binary_tree_at_2_levels() {
-        let tree_array = BoardingPass::generate_boarding_pass_tree(2);
-        let actual = tree_array.array.len();
-        assert_eq!(actual, 3); // creates 3 nodes
-    }
-
-    #[test]
-    fn create_binary_tree_at_3_levels() {
-        let tree_array = BoardingPass::generate_boarding_pass_tree(3);
-        let actual = tree_array.array.len();
-        assert_eq!(actual, 7);
-    }
-
-    #[test]
-    fn create_binary_tree_at_n_levels() {
-        let tree_array = BoardingPass::generate_boarding_pass_tree(8);
-        let actual = tree_array.array.len();
-        assert_eq!(actual, 255)
+        if let Some(n) = arena.get(child) {
+            assert_eq!(n.parent(), Some(root))
+        }
     }
 
     #[test]
@@ -401,6 +270,19 @@ mod tests {
         let snippet = "BFFFBBFRRR\r\nFFFBBBFRRR\r\nBBFFBBFRLL";
         assert_eq!(day05_1(snippet), 820);
     }
+
+    #[test]
+    fn should_draw_the_seat_map() {
+        let map = day05_seat_map("FFFFFFFLLL\r\nFFFFFFFLLR\r\nFFFFFFFLRR\r\nnope");
+        let lines: Vec<&str> = map.lines().collect();
+        assert_eq!(
+            lines[0],
+            "line 4: error parsing boarding pass: invalid length. expected 10, found: 4"
+        );
+        assert_eq!(lines[5], "free seat: Some(2)");
+        assert_eq!(lines[6], "   0 ##X# ....  <- free seat 2 (FFFFFFFLRL)");
+        assert_eq!(lines.len(), 6 + 128);
+    }
 }
 
 /*
@@ -0,0 +1,324 @@
+/*
+Turns out the "tree" from the seat puzzle is really just binary counting in disguise.
+Every character halves the remaining range, so the lower half letter is a 0 bit and the upper half
+letter is a 1 bit, read most significant bit first:
+  F B F B B F F -> 0 1 0 1 1 0 0 -> 44
+No arena needed, and the same trick works for any pair of letters and any power of two.
+*/
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Decodes one axis of a binary space partition, e.g. the 7 `F`/`B` characters of a row.
+/// `lower` keeps the lower half of the range (a 0 bit), `upper` keeps the upper half (a 1 bit).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BspDecoder {
+    lower: char,
+    upper: char,
+    bits: u32,
+}
+
+impl BspDecoder {
+    /// Builds a decoder for a user supplied alphabet and axis size. The size has to be a power of two
+    /// because every character splits the range exactly in half.
+    pub fn for_size(lower: char, upper: char, size: u32) -> Result<BspDecoder, BspError> {
+        if lower == upper {
+            return Err(BspError::InvalidAlphabet(lower));
+        }
+        if size < 2 || !size.is_power_of_two() {
+            return Err(BspError::InvalidSize(size));
+        }
+        Ok(BspDecoder {
+            lower,
+            upper,
+            bits: size.trailing_zeros(),
+        })
+    }
+
+    /// How many rows (or columns) this axis splits into.
+    pub fn size(&self) -> u32 {
+        1 << self.bits
+    }
+
+    pub fn decode(&self, s: &str) -> Result<u32, BspError> {
+        let found = s.chars().count();
+        if found != self.bits as usize {
+            return Err(BspError::InvalidLength {
+                expected: self.bits as usize,
+                found,
+            });
+        }
+        s.chars().enumerate().try_fold(0, |value, (position, c)| {
+            if c == self.lower {
+                Ok(value << 1)
+            } else if c == self.upper {
+                Ok((value << 1) | 1)
+            } else {
+                Err(BspError::InvalidChar {
+                    found: c,
+                    position,
+                    expected: [self.lower, self.upper],
+                })
+            }
+        })
+    }
+
+    /// The reverse of `decode`: 44 with `F`/`B` over 7 bits gives back `FBFBBFF`.
+    pub fn encode(&self, value: u32) -> Result<String, BspError> {
+        if value >= self.size() {
+            return Err(BspError::OutOfRange {
+                value,
+                size: self.size(),
+            });
+        }
+        Ok((0..self.bits)
+            .rev()
+            .map(|bit| {
+                if (value >> bit) & 1 == 1 {
+                    self.upper
+                } else {
+                    self.lower
+                }
+            })
+            .collect())
+    }
+}
+
+/// A whole plane: the row characters come first, then the column characters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub rows: BspDecoder,
+    pub columns: BspDecoder,
+}
+
+impl Plane {
+    /// Seat ids are u32, so the row and column bits together can't go past 32.
+    pub fn new(rows: BspDecoder, columns: BspDecoder) -> Result<Plane, BspError> {
+        let bits = rows.bits + columns.bits;
+        if bits > 32 {
+            return Err(BspError::TooManyBits(bits));
+        }
+        Ok(Plane { rows, columns })
+    }
+
+    /// How many seats there are, which is one more than the biggest seat id.
+    pub fn seats(&self) -> u64 {
+        1 << self.pass_len()
+    }
+
+    /// Number of characters in a boarding pass for this plane.
+    pub fn pass_len(&self) -> usize {
+        (self.rows.bits + self.columns.bits) as usize
+    }
+
+    pub fn decode(&self, pass: &str) -> Result<(u32, u32), BspError> {
+        let found = pass.chars().count();
+        if found != self.pass_len() {
+            return Err(BspError::InvalidLength {
+                expected: self.pass_len(),
+                found,
+            });
+        }
+        let row_bits = self.rows.bits as usize;
+        let split = pass
+            .char_indices()
+            .nth(row_bits)
+            .map_or(pass.len(), |(idx, _)| idx);
+        let row = self.rows.decode(&pass[..split])?;
+        let column = self.columns.decode(&pass[split..]).map_err(|e| match e {
+            BspError::InvalidChar {
+                found,
+                position,
+                expected,
+            } => BspError::InvalidChar {
+                found,
+                position: position + row_bits,
+                expected,
+            },
+            other => other,
+        })?;
+        Ok((row, column))
+    }
+
+    pub fn encode(&self, row: u32, column: u32) -> Result<String, BspError> {
+        Ok(self.rows.encode(row)? + &self.columns.encode(column)?)
+    }
+
+    /// Can't overflow: a row and column inside the plane fit in the plane's 32 bits at most.
+    pub fn seat_id(&self, row: u32, column: u32) -> Result<u32, BspError> {
+        for (value, size) in [(row, self.rows.size()), (column, self.columns.size())] {
+            if value >= size {
+                return Err(BspError::OutOfRange { value, size });
+            }
+        }
+        Ok(row * self.columns.size() + column)
+    }
+}
+
+impl Default for Plane {
+    /// The puzzle's plane: 128 rows (`F`/`B`) by 8 columns (`L`/`R`).
+    fn default() -> Self {
+        let rows = BspDecoder::for_size('F', 'B', 128).expect("128 rows is a power of two");
+        let columns = BspDecoder::for_size('L', 'R', 8).expect("8 columns is a power of two");
+        Plane::new(rows, columns).expect("10 bits fit in a seat id")
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BspError {
+    InvalidAlphabet(char),
+    InvalidSize(u32),
+    InvalidLength {
+        expected: usize,
+        found: usize,
+    },
+    InvalidChar {
+        found: char,
+        position: usize,
+        expected: [char; 2],
+    },
+    OutOfRange {
+        value: u32,
+        size: u32,
+    },
+    /// The plane has more seats than a u32 seat id can count
+    TooManyBits(u32),
+}
+
+impl Display for BspError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BspError::InvalidAlphabet(c) => {
+                write!(f, "lower and upper letters are both '{}'", c)
+            }
+            BspError::InvalidSize(size) => {
+                write!(f, "size must be a power of two above 1, found: {}", size)
+            }
+            BspError::InvalidLength { expected, found } => {
+                write!(f, "invalid length. expected {}, found: {}", expected, found)
+            }
+            BspError::InvalidChar {
+                found,
+                position,
+                expected: [lower, upper],
+            } => write!(
+                f,
+                "invalid char '{}' at position {} (expected {} or {})",
+                found, position, lower, upper
+            ),
+            BspError::OutOfRange { value, size } => {
+                write!(f, "{} is out of range for a size of {}", value, size)
+            }
+            BspError::TooManyBits(bits) => {
+                write!(
+                    f,
+                    "a plane can have at most 32 bits of seats, found: {}",
+                    bits
+                )
+            }
+        }
+    }
+}
+
+impl Error for BspError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_decode_rows_and_columns() {
+        let plane = Plane::default();
+        assert_eq!(plane.rows.decode("FBFBBFF"), Ok(44));
+        assert_eq!(plane.columns.decode("RLR"), Ok(5));
+        assert_eq!(plane.decode("BBFFBBFRLL"), Ok((102, 4)));
+        assert_eq!(plane.seat_id(102, 4), Ok(820));
+        assert_eq!(
+            plane.seat_id(128, 0),
+            Err(BspError::OutOfRange {
+                value: 128,
+                size: 128
+            })
+        );
+    }
+
+    #[test]
+    fn it_should_encode_back_to_a_boarding_pass() {
+        let plane = Plane::default();
+        assert_eq!(plane.encode(44, 5), Ok("FBFBBFFRLR".to_string()));
+        for pass in &["BFFFBBFRRR", "FFFBBBFRRR", "BBFFBBFRLL"] {
+            let (row, column) = plane.decode(pass).unwrap();
+            assert_eq!(plane.encode(row, column).as_deref(), Ok(*pass));
+        }
+        assert_eq!(
+            plane.encode(128, 0),
+            Err(BspError::OutOfRange {
+                value: 128,
+                size: 128
+            })
+        );
+    }
+
+    #[test]
+    fn it_should_support_any_alphabet_and_size() {
+        let rows = BspDecoder::for_size('0', '1', 16).unwrap();
+        let columns = BspDecoder::for_size('<', '>', 4).unwrap();
+        let plane = Plane::new(rows, columns).unwrap();
+        assert_eq!(plane.pass_len(), 6);
+        assert_eq!(plane.decode("1010><"), Ok((10, 2)));
+        assert_eq!(plane.seat_id(10, 2), Ok(42));
+        assert_eq!(plane.seats(), 64);
+        assert_eq!(
+            BspDecoder::for_size('0', '1', 12),
+            Err(BspError::InvalidSize(12))
+        );
+        assert_eq!(
+            BspDecoder::for_size('x', 'x', 8),
+            Err(BspError::InvalidAlphabet('x'))
+        );
+    }
+
+    #[test]
+    fn it_should_keep_seat_ids_inside_32_bits() {
+        let half = BspDecoder::for_size('0', '1', 1 << 16).unwrap();
+        let plane = Plane::new(half, half).unwrap();
+        assert_eq!(plane.seats(), 1 << 32);
+        assert_eq!(plane.seat_id(65535, 65535), Ok(u32::MAX));
+
+        let rows = BspDecoder::for_size('0', '1', 1 << 31).unwrap();
+        let columns = BspDecoder::for_size('L', 'R', 4).unwrap();
+        assert_eq!(Plane::new(rows, columns), Err(BspError::TooManyBits(33)));
+        assert_eq!(
+            BspError::TooManyBits(33).to_string(),
+            "a plane can have at most 32 bits of seats, found: 33"
+        );
+    }
+
+    #[test]
+    fn it_should_report_where_the_bad_char_is() {
+        let plane = Plane::default();
+        assert_eq!(
+            plane.decode("FBFABFFRLR"),
+            Err(BspError::InvalidChar {
+                found: 'A',
+                position: 3,
+                expected: ['F', 'B']
+            })
+        );
+        assert_eq!(
+            plane.decode("FBFBBFFRZR"),
+            Err(BspError::InvalidChar {
+                found: 'Z',
+                position: 8,
+                expected: ['L', 'R']
+            })
+        );
+        assert_eq!(
+            plane.decode("FBFBBFFRL"),
+            Err(BspError::InvalidLength {
+                expected: 10,
+                found: 9
+            })
+        );
+    }
+}
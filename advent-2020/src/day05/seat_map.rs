@@ -0,0 +1,234 @@
+use super::bsp::Plane;
+use super::{BoardingPass, ParseBoardingPassError};
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Every boarding pass from the input laid out over the plane, so we can see who sits where
+/// (and which passes make no sense).
+pub struct SeatMap {
+    plane: Plane,
+    /// How many boarding passes claim each seat, indexed by seat id
+    taken: Vec<u32>,
+    /// Line number (starting at 1) of each line that isn't a boarding pass, and why
+    invalid: Vec<(usize, ParseBoardingPassError)>,
+}
+
+impl SeatMap {
+    pub fn from_input(input: &str) -> SeatMap {
+        let plane = Plane::default();
+        let mut taken = vec![0; plane.seats() as usize];
+        let mut invalid = Vec::new();
+        for (idx, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match BoardingPass::from_str(line) {
+                Ok(bp) => taken[bp.seat_id as usize] += 1,
+                Err(e) => invalid.push((idx + 1, e)),
+            }
+        }
+        SeatMap {
+            plane,
+            taken,
+            invalid,
+        }
+    }
+
+    pub fn invalid_passes(&self) -> &[(usize, ParseBoardingPassError)] {
+        &self.invalid
+    }
+
+    /// Seat ids claimed by more than one boarding pass.
+    pub fn duplicates(&self) -> Vec<u32> {
+        self.seat_ids()
+            .filter(|&id| self.taken[id as usize] > 1)
+            .collect()
+    }
+
+    /// Rows at the very front of the plane nobody is sitting in.
+    pub fn missing_front_rows(&self) -> Range<u32> {
+        let first = (0..self.plane.rows.size())
+            .find(|&row| !self.is_row_empty(row))
+            .unwrap_or_else(|| self.plane.rows.size());
+        0..first
+    }
+
+    /// Rows at the very back of the plane nobody is sitting in.
+    /// If the whole plane is empty those rows are already counted at the front.
+    pub fn missing_back_rows(&self) -> Range<u32> {
+        let rows = self.plane.rows.size();
+        let last = (0..rows)
+            .rev()
+            .find(|&row| !self.is_row_empty(row))
+            .map_or(rows, |row| row + 1);
+        last..rows
+    }
+
+    /// Empty seats somewhere between the first and the last taken seat.
+    pub fn gaps(&self) -> Vec<u32> {
+        let first = self.seat_ids().find(|&id| self.is_taken(id));
+        let last = self.seat_ids().rev().find(|&id| self.is_taken(id));
+        match (first, last) {
+            (Some(first), Some(last)) => (first..last).filter(|&id| !self.is_taken(id)).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Our seat is the gap with both of its neighbours taken.
+    pub fn free_seat(&self) -> Option<u32> {
+        // gaps never contain the first or last taken seat, so id - 1 and id + 1 are always in range
+        self.gaps()
+            .into_iter()
+            .find(|&id| self.is_taken(id - 1) && self.is_taken(id + 1))
+    }
+
+    /// Draws the plane one row per line, front to back, with the aisle down the middle:
+    /// `#` taken, `D` claimed by more than one pass, `.` empty and `X` our free seat.
+    pub fn render(&self) -> String {
+        let columns = self.plane.columns.size();
+        let free_seat = self.free_seat();
+        let mut out = String::new();
+        for row in 0..self.plane.rows.size() {
+            out.push_str(&format!("{:>4} ", row));
+            for column in 0..columns {
+                if column == columns / 2 {
+                    out.push(' ');
+                }
+                let id = self.seat_id(row, column);
+                out.push(match self.taken[id as usize] {
+                    _ if Some(id) == free_seat => 'X',
+                    0 => '.',
+                    1 => '#',
+                    _ => 'D',
+                });
+            }
+            if let Some(id) = free_seat.filter(|&id| id / columns == row) {
+                if let Ok(pass) = self.plane.encode(row, id % columns) {
+                    out.push_str(&format!("  <- free seat {} ({})", id, pass));
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn seat_ids(&self) -> Range<u32> {
+        0..self.taken.len() as u32
+    }
+
+    fn is_taken(&self, seat_id: u32) -> bool {
+        self.taken[seat_id as usize] > 0
+    }
+
+    fn is_row_empty(&self, row: u32) -> bool {
+        let columns = self.plane.columns.size();
+        (0..columns).all(|column| !self.is_taken(self.seat_id(row, column)))
+    }
+
+    fn seat_id(&self, row: u32, column: u32) -> u32 {
+        self.plane
+            .seat_id(row, column)
+            .expect("rows and columns come from the plane")
+    }
+}
+
+impl Display for SeatMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (line, error) in self.invalid_passes() {
+            writeln!(f, "line {}: {}", line, error)?;
+        }
+        writeln!(f, "duplicate seats: {:?}", self.duplicates())?;
+        writeln!(
+            f,
+            "missing rows at the front: {:?}",
+            self.missing_front_rows()
+        )?;
+        writeln!(
+            f,
+            "missing rows at the back: {:?}",
+            self.missing_back_rows()
+        )?;
+        writeln!(f, "gaps: {:?}", self.gaps())?;
+        writeln!(f, "free seat: {:?}", self.free_seat())?;
+        write!(f, "{}", self.render())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fills rows 2 through 4 except seat 25, then adds whatever extra lines are given.
+    fn plane_with_one_free_seat(extra: &[&str]) -> String {
+        let plane = Plane::default();
+        let mut lines: Vec<String> = (16..40)
+            .filter(|&id| id != 25)
+            .map(|id| plane.encode(id / 8, id % 8).unwrap())
+            .collect();
+        lines.extend(extra.iter().map(|s| s.to_string()));
+        lines.join("\r\n")
+    }
+
+    #[test]
+    fn it_should_find_the_free_seat() {
+        let seat_map = SeatMap::from_input(&plane_with_one_free_seat(&[]));
+        assert_eq!(seat_map.gaps(), vec![25]);
+        assert_eq!(seat_map.free_seat(), Some(25));
+        assert!(seat_map.duplicates().is_empty());
+        assert!(seat_map.invalid_passes().is_empty());
+    }
+
+    #[test]
+    fn it_should_find_missing_rows_at_the_front_and_back() {
+        let seat_map = SeatMap::from_input(&plane_with_one_free_seat(&[]));
+        assert_eq!(seat_map.missing_front_rows(), 0..2);
+        assert_eq!(seat_map.missing_back_rows(), 5..128);
+
+        let empty = SeatMap::from_input("");
+        assert_eq!(empty.missing_front_rows(), 0..128);
+        assert_eq!(empty.missing_back_rows(), 128..128);
+        assert_eq!(empty.free_seat(), None);
+    }
+
+    #[test]
+    fn it_should_report_duplicates_and_invalid_passes() {
+        // FFFFBFFLLL is seat 32, which is already taken
+        let input = plane_with_one_free_seat(&["FFFFBFFLLL", "FFFFBFFLL", "FFFFBFFLLX"]);
+        let seat_map = SeatMap::from_input(&input);
+        assert_eq!(seat_map.duplicates(), vec![32]);
+
+        let invalid: Vec<(usize, String)> = seat_map
+            .invalid_passes()
+            .iter()
+            .map(|(line, e)| (*line, e.to_string()))
+            .collect();
+        assert_eq!(
+            invalid,
+            vec![
+                (
+                    25,
+                    "error parsing boarding pass: invalid length. expected 10, found: 9".to_string()
+                ),
+                (
+                    26,
+                    "error parsing boarding pass: invalid char 'X' at position 9 (expected L or R) in: FFFFBFFLLX"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_render_the_plane() {
+        let input = plane_with_one_free_seat(&["FFFFBFFLLL"]);
+        let rendered = SeatMap::from_input(&input).render();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 128);
+        assert_eq!(lines[1], "   1 .... ....");
+        assert_eq!(lines[2], "   2 #### ####");
+        assert_eq!(lines[3], "   3 #X## ####  <- free seat 25 (FFFFFBBLLR)");
+        assert_eq!(lines[4], "   4 D### ####");
+    }
+}
@@ -0,0 +1,145 @@
+/*
+The seat tree the puzzle was first solved with, kept (minus its unused fields) so the BSP decoder
+has something to be benchmarked against. Every call builds a breadth-first array of 2^levels - 1
+nodes and walks down it one character at a time, F/L to the left child and B/R to the right.
+Nothing outside the tests uses it, so it's only compiled for them.
+*/
+
+struct TreeArray {
+    array: Vec<u32>,
+    index_value_offset: u32,
+}
+
+/// Generates a tree using a vector, breadth-first representation
+/// Wiki: https://en.wikipedia.org/wiki/Binary_tree#Arrays
+/// Levels here is referring to the depth of a perfect binary tree (2 children all internal nodes, and leaf nodes are at the same level).
+/// For example:
+///                       R
+///                   F      B
+///               F   B    F  B
+///              F B  F B  F B F B
+/// This tree's levels = 4.
+fn generate_boarding_pass_tree(levels: u32) -> TreeArray {
+    let base: u32 = 2;
+    let max_nodes = base.pow(levels) - 1;
+    let terminal_nodes = base.pow(levels - 1);
+    // index_value_offset is the left most value of lowest value, it's also the offset to get the numerical value of the row
+    let index_value_offset = max_nodes - terminal_nodes;
+
+    let tree_array: Vec<u32> = (0..max_nodes).collect();
+    TreeArray {
+        array: tree_array,
+        index_value_offset,
+    }
+}
+
+struct Node {
+    boarding_pass: String,
+    current: Option<u32>,
+    offset: u32,
+}
+
+impl Node {
+    fn new(tree_array: TreeArray, boarding_pass: &str) -> Node {
+        Node {
+            boarding_pass: boarding_pass.to_owned(),
+            current: Some(0),
+            offset: tree_array.index_value_offset,
+        }
+    }
+
+    fn traverse_tree_array(&mut self) {
+        for c in self.boarding_pass.chars() {
+            self.current = self.current.map(|num| {
+                if c == 'F' || c == 'L' {
+                    (2 * num) + 1
+                } else {
+                    (2 * num) + 2
+                }
+            });
+        }
+    }
+
+    fn get_current_value(&self) -> u32 {
+        self.current.unwrap_or_default()
+    }
+}
+
+/// What `find_the_row` (levels 8) and `find_the_column` (levels 4) used to do.
+fn find_with_tree(s: &str, levels: u32) -> u32 {
+    let mut node = Node::new(generate_boarding_pass_tree(levels), s);
+    node.traverse_tree_array();
+    node.get_current_value() - node.offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day05::bsp::Plane;
+    use std::time::Instant;
+
+    #[test]
+    fn it_should_build_a_perfect_tree() {
+        let sizes: Vec<usize> = [1, 2, 3, 8]
+            .iter()
+            .map(|&levels| generate_boarding_pass_tree(levels).array.len())
+            .collect();
+        assert_eq!(sizes, vec![1, 3, 7, 255]);
+    }
+
+    #[test]
+    fn tree_and_decoder_should_agree() {
+        let plane = Plane::default();
+        for row in 0..128 {
+            let pass = plane.rows.encode(row).unwrap();
+            assert_eq!(find_with_tree(&pass, 8), row);
+        }
+        for column in 0..8 {
+            let pass = plane.columns.encode(column).unwrap();
+            assert_eq!(find_with_tree(&pass, 4), column);
+        }
+    }
+
+    // Not a criterion benchmark (this is a binary crate, so benches/ can't reach the decoder),
+    // but it times both over every seat on the plane:
+    // cargo test --release day05::tree::tests::bench -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_decoder_against_tree() {
+        let plane = Plane::default();
+        let passes: Vec<String> = (0..128 * 8)
+            .map(|id| plane.encode(id / 8, id % 8).unwrap())
+            .collect();
+        let rounds = 200;
+
+        let start = Instant::now();
+        let mut tree_total = 0u64;
+        for _ in 0..rounds {
+            for pass in &passes {
+                let seat_id = find_with_tree(&pass[..7], 8) * 8 + find_with_tree(&pass[7..], 4);
+                tree_total += u64::from(seat_id);
+            }
+        }
+        let tree_time = start.elapsed();
+
+        let start = Instant::now();
+        let mut decoder_total = 0u64;
+        for _ in 0..rounds {
+            for pass in &passes {
+                let (row, column) = plane.decode(pass).unwrap();
+                decoder_total += u64::from(plane.seat_id(row, column).unwrap());
+            }
+        }
+        let decoder_time = start.elapsed();
+
+        println!(
+            "tree: {:?}, decoder: {:?} ({} passes x {} rounds)",
+            tree_time,
+            decoder_time,
+            passes.len(),
+            rounds
+        );
+        assert_eq!(tree_total, decoder_total);
+        assert!(decoder_time < tree_time);
+    }
+}
@@ -1,23 +1,21 @@
+mod answers;
+
+use answers::{histogram_across_groups, Group, InvalidAnswer};
 use std::borrow::Borrow;
-use std::collections::HashSet;
 
-pub(crate) fn day06_1(input: &str) -> usize {
+pub(crate) fn day06_1(input: &str) -> Result<usize, InvalidAnswer> {
     sum_up_all_yes_all_groups(input)
 }
 
-pub(crate) fn day06_2(input: &str) -> usize {
+pub(crate) fn day06_2(input: &str) -> Result<usize, InvalidAnswer> {
     sum_up_shared_yes_all_groups(input)
 }
 
-fn find_unique_yes_answers(group: &str) -> usize {
-    let mut set: HashSet<char> = HashSet::new();
-    group.chars().filter(|c| !c.is_whitespace()).for_each(|ch| {
-        set.insert(ch);
-    });
-    set.len()
+fn find_unique_yes_answers(group: &str) -> Result<usize, InvalidAnswer> {
+    Ok(Group::parse(group)?.anyone().len())
 }
 
-fn sum_up_all_yes_all_groups(groups: &str) -> usize {
+fn sum_up_all_yes_all_groups(groups: &str) -> Result<usize, InvalidAnswer> {
     let mut group_buffer = GroupBuffer {
         groups: Vec::new(),
         buffer: Vec::new(),
@@ -33,22 +31,12 @@ fn sum_up_all_yes_all_groups(groups: &str) -> usize {
 
 /// We still need to know all the unique letters that are appear in the group
 /// Then, we need to know the unique letters *shared* between all group members
-fn find_shared_yes_answers(group: &str) -> usize {
-    let mut set: HashSet<char> = HashSet::new();
-    let mut shared_set: HashSet<&char> = HashSet::new();
-    group.chars().filter(|c| !c.is_whitespace()).for_each(|ch| {
-        set.insert(ch);
-    });
-    let unique_chars = set.iter();
-    for unique_char in unique_chars {
-        if group.split_whitespace().all(|c| c.contains(*unique_char)) {
-            shared_set.insert(unique_char);
-        }
-    }
-    shared_set.len()
+/// Turns out that's just the intersection of everyone's answers
+fn find_shared_yes_answers(group: &str) -> Result<usize, InvalidAnswer> {
+    Ok(Group::parse(group)?.everyone().len())
 }
 
-fn sum_up_shared_yes_all_groups(groups: &str) -> usize {
+fn sum_up_shared_yes_all_groups(groups: &str) -> Result<usize, InvalidAnswer> {
     let mut group_buffer = GroupBuffer {
         groups: Vec::new(),
         buffer: Vec::new(),
@@ -62,6 +50,26 @@ fn sum_up_shared_yes_all_groups(groups: &str) -> usize {
         .sum()
 }
 
+/// How many people across all groups said "yes" to each question, one `question: count` per line.
+/// Run with `cargo run -- day06 histogram`.
+pub(crate) fn day06_histogram(input: &str) -> Result<String, InvalidAnswer> {
+    let mut group_buffer = GroupBuffer {
+        groups: Vec::new(),
+        buffer: Vec::new(),
+    };
+    group_buffer.consume(input.lines());
+    let groups = group_buffer
+        .groups
+        .iter()
+        .map(|group| Group::parse(group))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(histogram_across_groups(&groups)
+        .iter()
+        .zip('a'..='z')
+        .map(|(count, question)| format!("{}: {}\n", question, count))
+        .collect())
+}
+
 #[derive(Debug)]
 struct GroupBuffer {
     groups: Vec<String>,
@@ -69,7 +77,7 @@ struct GroupBuffer {
 }
 
 impl GroupBuffer {
-    fn consume<'a>(&'a mut self, lines: impl IntoIterator<Item = &'a str>) -> () {
+    fn consume<'a>(&'a mut self, lines: impl IntoIterator<Item = &'a str>) {
         for line in lines {
             if line.is_empty() {
                 self.groups.push(self.buffer.join("\n").to_string());
@@ -98,17 +106,17 @@ mod tests {
         let snippet_4 = "a\na\na\na";
         let snippet_5 = "b";
 
-        assert_eq!(find_unique_yes_answers(snippet_1), 3);
-        assert_eq!(find_unique_yes_answers(snippet_2), 3);
-        assert_eq!(find_unique_yes_answers(snippet_3), 3);
-        assert_eq!(find_unique_yes_answers(snippet_4), 1);
-        assert_eq!(find_unique_yes_answers(snippet_5), 1);
+        assert_eq!(find_unique_yes_answers(snippet_1), Ok(3));
+        assert_eq!(find_unique_yes_answers(snippet_2), Ok(3));
+        assert_eq!(find_unique_yes_answers(snippet_3), Ok(3));
+        assert_eq!(find_unique_yes_answers(snippet_4), Ok(1));
+        assert_eq!(find_unique_yes_answers(snippet_5), Ok(1));
     }
 
     #[test]
     fn it_should_return_unique_yeses_for_multiple_groups() {
         let snippet = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb";
-        assert_eq!(sum_up_all_yes_all_groups(snippet), 11);
+        assert_eq!(sum_up_all_yes_all_groups(snippet), Ok(11));
     }
 
     #[test]
@@ -124,6 +132,15 @@ mod tests {
         assert!(group_buffer.buffer.is_empty())
     }
 
+    #[test]
+    fn it_should_count_every_question_across_groups() {
+        let snippet = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb";
+        let histogram = day06_histogram(snippet).unwrap();
+        assert!(histogram.starts_with("a: 8\nb: 4\nc: 3\nd: 0\n"));
+        assert!(histogram.ends_with("z: 0\n"));
+        assert!(day06_histogram("ab\n\na?").is_err());
+    }
+
     #[test]
     fn it_should_return_shared_yes_in_a_group() {
         let snippet_1 = "abc";
@@ -132,16 +149,16 @@ mod tests {
         let snippet_4 = "a\na\na\na";
         let snippet_5 = "b";
 
-        assert_eq!(find_shared_yes_answers(snippet_1), 3);
-        assert_eq!(find_shared_yes_answers(snippet_2), 0);
-        assert_eq!(find_shared_yes_answers(snippet_3), 1);
-        assert_eq!(find_shared_yes_answers(snippet_4), 1);
-        assert_eq!(find_shared_yes_answers(snippet_5), 1);
+        assert_eq!(find_shared_yes_answers(snippet_1), Ok(3));
+        assert_eq!(find_shared_yes_answers(snippet_2), Ok(0));
+        assert_eq!(find_shared_yes_answers(snippet_3), Ok(1));
+        assert_eq!(find_shared_yes_answers(snippet_4), Ok(1));
+        assert_eq!(find_shared_yes_answers(snippet_5), Ok(1));
     }
 
     #[test]
     fn it_should_return_shared_yeses_for_multiple_groups() {
         let snippet = "abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb";
-        assert_eq!(sum_up_shared_yes_all_groups(snippet), 6);
+        assert_eq!(sum_up_shared_yes_all_groups(snippet), Ok(6));
     }
 }
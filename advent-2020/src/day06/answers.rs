@@ -0,0 +1,225 @@
+/*
+There are only 26 questions (a-z), so one person's answers fit in the bottom 26 bits of a u32.
+Then "anyone said yes" is OR, "everyone said yes" is AND, and counting is count_ones().
+*/
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::ops::{BitAnd, BitOr, BitXor};
+
+pub const QUESTIONS: usize = 26;
+
+/// A set of questions answered "yes", bit 0 is question `a` and bit 25 is question `z`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Answers(u32);
+
+impl Answers {
+    pub const NONE: Answers = Answers(0);
+
+    /// One person's line of answers. Whitespace is skipped, anything else has to be a question letter.
+    pub fn from_person(line: &str) -> Result<Answers, InvalidAnswer> {
+        line.chars()
+            .filter(|c| !c.is_whitespace())
+            .try_fold(Answers::NONE, |answers, c| match Self::bit(c) {
+                Some(bit) => Ok(Answers(answers.0 | bit)),
+                None => Err(InvalidAnswer {
+                    found: c,
+                    line: line.to_string(),
+                }),
+            })
+    }
+
+    fn bit(question: char) -> Option<u32> {
+        if question.is_ascii_lowercase() {
+            Some(1 << (question as u32 - 'a' as u32))
+        } else {
+            None
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn union(self, other: Answers) -> Answers {
+        Answers(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Answers) -> Answers {
+        Answers(self.0 & other.0)
+    }
+
+    pub fn symmetric_difference(self, other: Answers) -> Answers {
+        Answers(self.0 ^ other.0)
+    }
+}
+
+impl BitOr for Answers {
+    type Output = Answers;
+
+    fn bitor(self, rhs: Answers) -> Answers {
+        self.union(rhs)
+    }
+}
+
+impl BitAnd for Answers {
+    type Output = Answers;
+
+    fn bitand(self, rhs: Answers) -> Answers {
+        self.intersection(rhs)
+    }
+}
+
+impl BitXor for Answers {
+    type Output = Answers;
+
+    fn bitxor(self, rhs: Answers) -> Answers {
+        self.symmetric_difference(rhs)
+    }
+}
+
+/// A character that isn't one of the questions `a` to `z`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidAnswer {
+    found: char,
+    line: String,
+}
+
+impl Display for InvalidAnswer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "expected questions a to z, found '{}' in: {}",
+            self.found, self.line
+        )
+    }
+}
+
+impl Error for InvalidAnswer {}
+
+/// How many people answered "yes" to each question, index 0 is question `a`.
+pub type Histogram = [usize; QUESTIONS];
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Group {
+    members: Vec<Answers>,
+}
+
+impl Group {
+    /// A group is one person per line.
+    pub fn parse(group: &str) -> Result<Group, InvalidAnswer> {
+        Ok(Group {
+            members: group
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(Answers::from_person)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Questions anyone in the group answered "yes" to.
+    pub fn anyone(&self) -> Answers {
+        self.members.iter().fold(Answers::NONE, |acc, &a| acc | a)
+    }
+
+    /// Questions everyone in the group answered "yes" to. Nobody in the group means no questions.
+    pub fn everyone(&self) -> Answers {
+        self.at_least(self.members.len())
+    }
+
+    /// Questions at least `k` members answered "yes" to (a `k` of 0 counts as 1, questions nobody
+    /// answered are never included).
+    /// `at_least(1)` is the same as `anyone` and `at_least(members)` is the same as `everyone`.
+    pub fn at_least(&self, k: usize) -> Answers {
+        let histogram = self.histogram();
+        (0..QUESTIONS)
+            .filter(|&q| histogram[q] >= k.max(1))
+            .fold(Answers::NONE, |acc, q| acc | Answers(1 << q))
+    }
+
+    pub fn histogram(&self) -> Histogram {
+        let mut histogram = [0; QUESTIONS];
+        for member in &self.members {
+            for (q, count) in histogram.iter_mut().enumerate() {
+                if member.0 & (1 << q) != 0 {
+                    *count += 1;
+                }
+            }
+        }
+        histogram
+    }
+}
+
+/// Adds up every group's histogram, so index 0 is how many people across all groups said "yes" to `a`.
+pub fn histogram_across_groups(groups: &[Group]) -> Histogram {
+    groups.iter().fold([0; QUESTIONS], |mut total, group| {
+        for (sum, count) in total.iter_mut().zip(group.histogram().iter()) {
+            *sum += count;
+        }
+        total
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answers(s: &str) -> Answers {
+        Answers::from_person(s).unwrap()
+    }
+
+    #[test]
+    fn it_should_do_set_algebra() {
+        let ab = answers("ab");
+        let ac = answers("ac");
+        assert_eq!(ab | ac, answers("abc"));
+        assert_eq!(ab & ac, answers("a"));
+        assert_eq!(ab ^ ac, answers("bc"));
+        assert_eq!(answers("zza").len(), 2);
+        assert_eq!(answers(" \n"), Answers::NONE);
+    }
+
+    #[test]
+    fn it_should_reject_anything_but_questions() {
+        assert_eq!(answers("a b\tc"), answers("abc"));
+        assert_eq!(
+            Answers::from_person("abC").unwrap_err().to_string(),
+            "expected questions a to z, found 'C' in: abC"
+        );
+        assert!(Group::parse("ab\na1").is_err());
+    }
+
+    #[test]
+    fn it_should_answer_group_queries() {
+        let group = Group::parse("abc\nabd\nae").unwrap();
+        assert_eq!(group.anyone(), answers("abcde"));
+        assert_eq!(group.everyone(), answers("a"));
+        assert_eq!(group.at_least(2), answers("ab"));
+        assert_eq!(group.at_least(1), group.anyone());
+        assert_eq!(group.at_least(3), group.everyone());
+        assert_eq!(group.at_least(4), Answers::NONE);
+    }
+
+    #[test]
+    fn an_empty_group_should_have_no_answers() {
+        let group = Group::parse("").unwrap();
+        assert_eq!(group.anyone(), Answers::NONE);
+        assert_eq!(group.everyone(), Answers::NONE);
+        assert_eq!(group.at_least(0), Answers::NONE);
+    }
+
+    #[test]
+    fn it_should_build_histograms() {
+        let groups = vec![
+            Group::parse("ab\nac").unwrap(),
+            Group::parse("a\nz").unwrap(),
+        ];
+        let histogram = groups[0].histogram();
+        assert_eq!(&histogram[..4], &[2, 1, 1, 0]);
+
+        let total = histogram_across_groups(&groups);
+        assert_eq!(&total[..4], &[3, 1, 1, 0]);
+        assert_eq!(total[25], 1);
+        assert_eq!(total.iter().sum::<usize>(), 6);
+    }
+}
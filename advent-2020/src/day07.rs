@@ -1,177 +1,245 @@
-use std::collections::{HashMap, HashSet};
+mod dot;
+mod rules;
+
 extern crate pest;
 use self::pest::iterators::Pairs;
+pub(crate) use dot::DotScope;
+use pest::error::{ErrorVariant, LineColLocation};
 use pest::Parser;
+use rules::{BagRules, RuleError};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
 
-pub fn day07_1(input: &str) -> usize {
-    let all_bags = parse_many_bags_data(input);
-    find_possible_bag_holders_for_a_bag("shiny gold", &all_bags).len()
+pub fn day07_1(input: &str) -> Result<usize, LoadRulesError> {
+    let rules = load_valid_rules(input, "shiny gold")?;
+    Ok(rules.holders_of("shiny gold").len())
 }
 
-pub(crate) fn day07_2(input: &str) -> i32 {
+pub(crate) fn day07_2(input: &str) -> Result<u64, LoadRulesError> {
     /*
     bags must be color-coded and must contain specific quantities of other color-coded bags
     How many individual bags are required inside your single shiny gold bag?
     */
-    let all_bags = parse_many_bags_data(input);
-    let shiny_gold = get_bag_from_color("shiny gold", &all_bags);
-    count_bags_inside_smaller(&all_bags, shiny_gold, 0) - 1
+    let rules = load_valid_rules(input, "shiny gold")?;
+    rules
+        .count_bags_inside("shiny gold")
+        .map_err(|e| LoadRulesError::Invalid(vec![e]))
+}
+
+/// The rules as a Graphviz graph, see dot.rs
+pub(crate) fn day07_dot(input: &str, scope: DotScope) -> Result<String, LoadRulesError> {
+    let rules = BagRules::new(&parse_many_bags_data(input)?);
+    dot::to_dot(&rules, scope).map_err(|e| LoadRulesError::Invalid(vec![e]))
+}
+
+/// Every bad line and every problem with the rules, one per line. Run with `cargo run -- day07 check`.
+pub(crate) fn day07_check(input: &str) -> String {
+    let ParsedBags { bags, errors } = match parse_bags(input, ParseMode::Lenient) {
+        Ok(parsed) => parsed,
+        Err(e) => ParsedBags {
+            bags: Vec::new(),
+            errors: vec![e],
+        },
+    };
+    let mut report: Vec<String> = errors.iter().map(BagParseError::to_string).collect();
+    report.extend(
+        BagRules::new(&bags)
+            .diagnose("shiny gold")
+            .iter()
+            .map(RuleError::to_string),
+    );
+    report.join("\n")
+}
+
+/// Checks the rules before anybody starts counting, so a broken rule set is an error instead of a
+/// stack overflow or a quietly wrong answer.
+fn load_valid_rules(input: &str, root: &str) -> Result<BagRules, LoadRulesError> {
+    let rules = BagRules::new(&parse_many_bags_data(input)?);
+    rules.validate(root).map_err(LoadRulesError::Invalid)?;
+    Ok(rules)
 }
 
-fn parse_single_bag_data(rule: &str) -> Bag {
+fn parse_single_bag_data(rule: &str) -> Result<Bag, BagParseError> {
     let bag_data: Result<Pairs<Rule>, pest::error::Error<Rule>> = BagParser::parse(Rule::bag, rule);
     let mut color = "";
-    let mut contains: Vec<HashMap<String, String>> = Vec::new();
-    if let Ok(mut pairs) = bag_data {
-        if let Some(pair) = pairs.next() {
-            for innards in pair.into_inner() {
-                match innards.as_rule() {
-                    Rule::subject => {
-                        color = innards.as_str();
-                    }
-                    Rule::bag_item => {
-                        let mut contains_map: HashMap<String, String> = HashMap::new();
-                        for descriptor in innards.into_inner() {
-                            match descriptor.as_rule() {
-                                Rule::color => {
-                                    contains_map.insert(
-                                        "color".to_string(),
-                                        descriptor.as_str().to_string(),
-                                    );
-                                }
-                                Rule::quant => {
-                                    contains_map.insert(
-                                        "quantity".to_string(),
-                                        descriptor.as_str().to_string(),
-                                    );
-                                }
-                                _ => (),
+    let mut contains: Vec<BagItem> = Vec::new();
+    let mut pairs = bag_data.map_err(|e| BagParseError::from_pest(&e, rule))?;
+    if let Some(pair) = pairs.next() {
+        for innards in pair.into_inner() {
+            match innards.as_rule() {
+                Rule::subject => {
+                    color = innards.as_str();
+                }
+                Rule::bag_item => {
+                    let mut item_color = "";
+                    let mut quantity = 0;
+                    for descriptor in innards.into_inner() {
+                        match descriptor.as_rule() {
+                            Rule::color => {
+                                item_color = descriptor.as_str();
+                            }
+                            Rule::quant => {
+                                // the grammar only lets digits through, so this can only fail on overflow
+                                quantity =
+                                    descriptor.as_str().parse().map_err(|_| BagParseError {
+                                        line: 1,
+                                        column: descriptor.as_span().start() + 1,
+                                        expected: vec![Rule::quant],
+                                        text: rule.to_string(),
+                                    })?;
                             }
+                            _ => (),
                         }
-                        contains.push(contains_map);
                     }
-                    _ => (),
+                    contains.push(BagItem::new(quantity, item_color));
                 }
+                _ => (),
             }
-        };
-    }
+        }
+    };
 
-    Bag {
+    Ok(Bag {
         color: color.to_string(),
         contains,
-    }
+    })
 }
 
-fn parse_many_bags_data(bags: &str) -> Vec<Bag> {
-    let mut bags_list: Vec<Bag> = Vec::new();
-    let to_parse: Vec<&str> = bags.split('\n').map(str::trim).collect();
-    for maybe_bag in to_parse {
-        bags_list.push(parse_single_bag_data(maybe_bag))
-    }
-    bags_list
+/// Strict stops at the first line that doesn't parse, lenient skips bad lines and keeps going.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParseMode {
+    Strict,
+    Lenient,
 }
 
-fn find_direct_bag_holders_for_a_bag<'a, 'b>(color: &'b str, bags: &'a [Bag]) -> Vec<&'a Bag> {
-    bags.iter()
-        .filter(|&bag| {
-            bag.contains
-                .iter()
-                .any(|baguette| baguette.get("color").map_or(false, |col| color == col))
-        })
-        .collect()
+#[derive(Debug, PartialEq)]
+struct ParsedBags {
+    bags: Vec<Bag>,
+    /// Only ever filled in lenient mode
+    errors: Vec<BagParseError>,
 }
 
-fn find_possible_bag_holders_for_a_bag<'a, 'b>(
-    init_color: &'b str,
-    bags: &'a [Bag],
-) -> Vec<&'a Bag> {
-    let mut color_stack: Vec<&Bag> = Vec::new();
-    let mut possible_holders_stack: Vec<&'a Bag> = Vec::new();
-    let mut visited_colors = HashSet::new();
-    let mut done = false;
-    let mut color = init_color;
-
-    while !done {
-        if visited_colors.contains(color) {
-            if color_stack.is_empty() {
-                done = true;
-            } else {
-                let next_bag = color_stack.pop().unwrap();
-                color = next_bag.color.as_str();
-            }
+fn parse_bags(input: &str, mode: ParseMode) -> Result<ParsedBags, BagParseError> {
+    let mut parsed = ParsedBags {
+        bags: Vec::new(),
+        errors: Vec::new(),
+    };
+    for (idx, line) in input.split('\n').enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
             continue;
         }
-        let mut possibilities = find_direct_bag_holders_for_a_bag(color, bags);
-        color_stack.append(&mut possibilities);
-        visited_colors.insert(color);
-        if color_stack.is_empty() {
-            done = true;
-        } else {
-            let next_bag = color_stack.pop().unwrap();
-            possible_holders_stack.push(next_bag);
-            color = next_bag.color.as_str();
+        match parse_single_bag_data(line) {
+            Ok(bag) => parsed.bags.push(bag),
+            Err(e) => {
+                let e = e.on_line(idx + 1);
+                match mode {
+                    ParseMode::Strict => return Err(e),
+                    ParseMode::Lenient => parsed.errors.push(e),
+                }
+            }
         }
     }
-    possible_holders_stack
+    Ok(parsed)
 }
 
-fn count_bags_inside(input: &str, bag_color: &str) -> i8 {
-    let all_bags = parse_many_bags_data(input);
-    find_possible_bag_holders_for_a_bag("shiny gold", &all_bags);
-    /*
-    First we get our bag color which holds at least 1 type of bag, but could be multiple version of each type of bag
+fn parse_many_bags_data(bags: &str) -> Result<Vec<Bag>, BagParseError> {
+    parse_bags(bags, ParseMode::Strict).map(|parsed| parsed.bags)
+}
 
-     */
-    126
+/// Where a rule stopped matching bags.pest, and which grammar rules would have been accepted there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BagParseError {
+    line: usize,
+    column: usize,
+    expected: Vec<Rule>,
+    text: String,
 }
 
-// TODO: Explore in slightly more depth &Vec<_> to &[T] slice for type conversion
-fn count_bags_inside_smaller(all_bags: &[Bag], current_bag: Bag, mut amount: i32) -> i32 {
-    // Base case
-    if current_bag.contains.is_empty() {
-        amount += 1;
-        return amount;
+impl BagParseError {
+    fn from_pest(e: &pest::error::Error<Rule>, text: &str) -> BagParseError {
+        let column = match e.line_col {
+            LineColLocation::Pos((_, column)) => column,
+            LineColLocation::Span((_, column), _) => column,
+        };
+        let expected = match &e.variant {
+            ErrorVariant::ParsingError { positives, .. } => positives.clone(),
+            ErrorVariant::CustomError { .. } => Vec::new(),
+        };
+        BagParseError {
+            line: 1,
+            column,
+            expected,
+            text: text.to_string(),
+        }
+    }
+
+    /// We parse one line at a time, so pest always thinks it's on line 1
+    fn on_line(self, line: usize) -> BagParseError {
+        BagParseError { line, ..self }
     }
+}
 
-    for innard in current_bag.contains.iter() {
-        let quant = innard.get("quantity").unwrap();
-        let color = innard.get("color").unwrap();
-        // I tried to do recursion here and I failed initially ~~
-        // So it seems the issue here was that I was passing the amount dynamically as the accumulator
-        // Seems like the correct thing to do is to always have it start at 0
-        // And I probably would have done it correctly if I had a different variable
-        // Maybe we needed a "start_ammount" variable and then accumulator variable. I think that's how some recursions do it
-        amount += quant.parse().unwrap_or(0)
-            * count_bags_inside_smaller(all_bags, get_bag_from_color(color, all_bags), 0);
+impl Display for BagParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        if self.expected.is_empty() {
+            write!(f, "unexpected input")?;
+        } else {
+            let expected: Vec<String> = self.expected.iter().map(|r| format!("{:?}", r)).collect();
+            write!(f, "expected {}", expected.join(" or "))?;
+        }
+        write!(f, " in: {}", self.text)
     }
-    amount += 1;
-    amount
 }
 
-fn get_bag_from_color(color: &str, all_bags: &[Bag]) -> Bag {
-    let default_bag = Bag {
-        color: color.to_string(),
-        contains: Vec::new(),
-    };
+impl Error for BagParseError {}
 
-    all_bags
-        .iter()
-        .find(|&b| b.color == color)
-        .unwrap_or(&default_bag)
-        .to_owned()
+/// Anything that stops us from trusting the bag rules enough to answer
+#[derive(Debug, PartialEq)]
+pub enum LoadRulesError {
+    Parse(BagParseError),
+    Invalid(Vec<RuleError>),
 }
 
-#[derive(Debug, PartialEq)]
+impl From<BagParseError> for LoadRulesError {
+    fn from(e: BagParseError) -> Self {
+        LoadRulesError::Parse(e)
+    }
+}
+
+impl Display for LoadRulesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadRulesError::Parse(e) => write!(f, "{}", e),
+            LoadRulesError::Invalid(errors) => {
+                let errors: Vec<String> = errors.iter().map(RuleError::to_string).collect();
+                write!(f, "{}", errors.join("\n"))
+            }
+        }
+    }
+}
+
+impl Error for LoadRulesError {}
+
+#[derive(Debug, Clone, PartialEq)]
 struct Bag {
     color: String,
-    contains: Vec<HashMap<String, String>>,
+    contains: Vec<BagItem>,
+}
+
+/// One "2 muted yellow bags" entry of a rule
+#[derive(Debug, Clone, PartialEq)]
+struct BagItem {
+    quantity: u32,
+    color: String,
 }
 
-impl Clone for Bag {
-    fn clone(&self) -> Self {
-        let color = self.color.clone();
-        let contains = self.contains.clone();
-        Self { color, contains }
+impl BagItem {
+    fn new(quantity: u32, color: &str) -> BagItem {
+        BagItem {
+            quantity,
+            color: color.to_string(),
+        }
     }
 }
 
@@ -186,20 +254,64 @@ mod tests {
     #[test]
     fn it_can_parse_bags_and_what_they_contain() {
         let snippet = "light red bags contain 1 bright white bag, 2 muted yellow bags.";
-        let mut bright_white = HashMap::new();
-        bright_white.insert(String::from("color"), "bright white".to_string());
-        bright_white.insert("quantity".to_string(), "1".to_string());
-
-        let mut muted_yellow = HashMap::new();
-        muted_yellow.insert("color".to_string(), "muted yellow".to_string());
-        muted_yellow.insert("quantity".to_string(), "2".to_string());
-
         let expected = Bag {
             color: "light red".to_string(),
-            contains: vec![bright_white, muted_yellow],
+            contains: vec![
+                BagItem::new(1, "bright white"),
+                BagItem::new(2, "muted yellow"),
+            ],
         };
 
-        assert_eq!(parse_single_bag_data(snippet), expected)
+        assert_eq!(parse_single_bag_data(snippet), Ok(expected))
+    }
+
+    #[test]
+    fn it_should_say_where_a_rule_went_wrong() {
+        let snippet = "light red bags contain one bright white bag.";
+        let error = parse_single_bag_data(snippet).unwrap_err();
+        assert_eq!(
+            error,
+            BagParseError {
+                line: 1,
+                column: 24,
+                expected: vec![Rule::nocontains, Rule::quant],
+                text: snippet.to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 24: expected nocontains or quant in: light red bags contain one bright white bag."
+        );
+        assert_eq!(
+            parse_single_bag_data("light red bags contain 99999999999 bright white bags.")
+                .unwrap_err()
+                .expected,
+            vec![Rule::quant]
+        );
+    }
+
+    #[test]
+    fn strict_mode_should_stop_at_the_first_bad_line() {
+        let snippet = "light red bags contain 1 bright white bag.
+bright white bags hold 1 shiny gold bag.
+faded blue bags contian no other bags.";
+        let error = parse_many_bags_data(snippet).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.column, 13);
+        assert!(matches!(day07_1(snippet), Err(LoadRulesError::Parse(_))));
+    }
+
+    #[test]
+    fn lenient_mode_should_collect_every_bad_line() {
+        let snippet = "light red bags contain 1 bright white bag.
+bright white bags hold 1 shiny gold bag.
+faded blue bags contian no other bags.
+dotted black bags contain no other bags.";
+        let parsed = parse_bags(snippet, ParseMode::Lenient).unwrap();
+        let colors: Vec<&str> = parsed.bags.iter().map(|b| b.color.as_str()).collect();
+        assert_eq!(colors, vec!["light red", "dotted black"]);
+        let lines: Vec<(usize, usize)> = parsed.errors.iter().map(|e| (e.line, e.column)).collect();
+        assert_eq!(lines, vec![(2, 13), (3, 11)]);
     }
 
     #[test]
@@ -208,26 +320,17 @@ mod tests {
 bright white bags contain 1 shiny gold bag.
 faded blue bags contain no other bags.";
 
-        let mut bright_white = HashMap::new();
-        bright_white.insert(String::from("color"), "bright white".to_string());
-        bright_white.insert("quantity".to_string(), "1".to_string());
-
-        let mut muted_yellow = HashMap::new();
-        muted_yellow.insert("color".to_string(), "muted yellow".to_string());
-        muted_yellow.insert("quantity".to_string(), "2".to_string());
-
         let bag_1 = Bag {
             color: "light red".to_string(),
-            contains: vec![bright_white, muted_yellow],
+            contains: vec![
+                BagItem::new(1, "bright white"),
+                BagItem::new(2, "muted yellow"),
+            ],
         };
 
-        let mut shiny_gold = HashMap::new();
-        shiny_gold.insert(String::from("color"), "shiny gold".to_string());
-        shiny_gold.insert("quantity".to_string(), "1".to_string());
-
         let bag_2 = Bag {
             color: "bright white".to_string(),
-            contains: vec![shiny_gold],
+            contains: vec![BagItem::new(1, "shiny gold")],
         };
 
         let bag_3 = Bag {
@@ -237,7 +340,7 @@ faded blue bags contain no other bags.";
 
         let expected = vec![bag_1, bag_2, bag_3];
         let actual = parse_many_bags_data(snippet);
-        assert_eq!(actual, expected);
+        assert_eq!(actual, Ok(expected));
     }
 
     #[test]
@@ -246,50 +349,14 @@ faded blue bags contain no other bags.";
 bright white bags contain 1 shiny gold bag.
 faded blue bags contain 1 bright white bag.";
 
-        let mut bright_white = HashMap::new();
-        bright_white.insert(String::from("color"), "bright white".to_string());
-        bright_white.insert("quantity".to_string(), "1".to_string());
-
-        let mut bright_white_2 = HashMap::new();
-        bright_white_2.insert(String::from("color"), "bright white".to_string());
-        bright_white_2.insert("quantity".to_string(), "1".to_string());
-
-        let mut muted_yellow = HashMap::new();
-        muted_yellow.insert("color".to_string(), "muted yellow".to_string());
-        muted_yellow.insert("quantity".to_string(), "2".to_string());
-
-        let bag_1 = Bag {
-            color: "light red".to_string(),
-            contains: vec![bright_white, muted_yellow],
-        };
-
-        let mut shiny_gold = HashMap::new();
-        shiny_gold.insert(String::from("color"), "shiny gold".to_string());
-        shiny_gold.insert("quantity".to_string(), "1".to_string());
-
-        let bag_2 = Bag {
-            color: "bright white".to_string(),
-            contains: vec![shiny_gold],
-        };
-
-        let bag_3 = Bag {
-            color: "faded blue".to_string(),
-            contains: vec![bright_white_2],
-        };
-
-        let expected = vec![&bag_1, &bag_3];
-        let all_bags = parse_many_bags_data(snippet);
-        let actual = find_direct_bag_holders_for_a_bag("bright white", &all_bags);
-        assert_eq!(actual, expected);
+        let rules = BagRules::new(&parse_many_bags_data(snippet).unwrap());
         assert_eq!(
-            find_direct_bag_holders_for_a_bag("shiny gold", &all_bags),
-            vec![&bag_2]
-        );
-        let no_bag: Vec<&Bag> = Vec::new();
-        assert_eq!(
-            find_direct_bag_holders_for_a_bag("unreal", &all_bags),
-            no_bag
+            rules.direct_holders_of("bright white"),
+            vec!["light red", "faded blue"]
         );
+        assert_eq!(rules.direct_holders_of("shiny gold"), vec!["bright white"]);
+        let no_bag: Vec<&str> = Vec::new();
+        assert_eq!(rules.direct_holders_of("unreal"), no_bag);
     }
 
     #[test]
@@ -298,41 +365,10 @@ faded blue bags contain 1 bright white bag.";
 bright white bags contain 1 shiny gold bag.
 faded blue bags contain 1 bright white bag.";
 
-        let mut bright_white = HashMap::new();
-        bright_white.insert(String::from("color"), "bright white".to_string());
-        bright_white.insert("quantity".to_string(), "1".to_string());
-
-        let mut bright_white_2 = HashMap::new();
-        bright_white_2.insert(String::from("color"), "bright white".to_string());
-        bright_white_2.insert("quantity".to_string(), "1".to_string());
-
-        let mut muted_yellow = HashMap::new();
-        muted_yellow.insert("color".to_string(), "muted yellow".to_string());
-        muted_yellow.insert("quantity".to_string(), "2".to_string());
-
-        let bag_1 = Bag {
-            color: "light red".to_string(),
-            contains: vec![bright_white, muted_yellow],
-        };
-
-        let mut shiny_gold = HashMap::new();
-        shiny_gold.insert(String::from("color"), "shiny gold".to_string());
-        shiny_gold.insert("quantity".to_string(), "1".to_string());
-
-        let bag_2 = Bag {
-            color: "bright white".to_string(),
-            contains: vec![shiny_gold],
-        };
-
-        let bag_3 = Bag {
-            color: "faded blue".to_string(),
-            contains: vec![bright_white_2],
-        };
-
-        let expected = vec![&bag_2, &bag_3, &bag_1];
-        let all_bags = parse_many_bags_data(snippet);
-        let actual = find_possible_bag_holders_for_a_bag("shiny gold", &all_bags);
-        assert_eq!(actual, expected);
+        let rules = BagRules::new(&parse_many_bags_data(snippet).unwrap());
+        let mut actual = rules.holders_of("shiny gold");
+        actual.sort_unstable();
+        assert_eq!(actual, vec!["bright white", "faded blue", "light red"]);
     }
 
     #[test]
@@ -344,27 +380,21 @@ dark yellow bags contain 2 dark green bags.
 dark green bags contain 2 dark blue bags.
 dark blue bags contain 2 dark violet bags.
 dark violet bags contain no other bags.";
-        assert_eq!(count_bags_inside(snippet, "shiny gold"), 126);
+        assert_eq!(day07_2(snippet), Ok(126));
+        assert_eq!(day07_check(snippet), "");
 
-        let all_bags = parse_many_bags_data(snippet);
-        assert_eq!(
-            count_bags_inside_smaller(&all_bags, get_bag_from_color("shiny gold", &all_bags), 0)
-                - 1,
-            126
-        );
+        let rules = BagRules::new(&parse_many_bags_data(snippet).unwrap());
+        assert_eq!(rules.count_bags_inside("shiny gold"), Ok(126));
     }
 
     #[test]
     fn should_count_how_many_bags_are_required_inside_smaller_bag() {
-        // Don't count the shiny gold bag itself, so subtracting 1 makes sense here
+        // dark orange has no rule of its own, so it holds nothing
         let snippet = "shiny gold bags contain 2 dark red bags.
 dark red bags contain 2 dark orange bags.";
-        let all_bags = parse_many_bags_data(snippet);
-        assert_eq!(
-            count_bags_inside_smaller(&all_bags, get_bag_from_color("shiny gold", &all_bags), 0)
-                - 1,
-            6
-        );
+        let rules = BagRules::new(&parse_many_bags_data(snippet).unwrap());
+        assert_eq!(rules.count_bags_inside("shiny gold"), Ok(6));
+        assert!(day07_2(snippet).is_err(), "dark orange is never defined");
     }
 
     #[test]
@@ -381,11 +411,8 @@ dark red bags contain 2 dark orange bags.";
         faded blue bags contain no other bags.
         dotted black bags contain no other bags.";
 
-        let all_bags = parse_many_bags_data(snippet);
-        assert_eq!(
-            count_bags_inside_smaller(&all_bags, get_bag_from_color("shiny gold", &all_bags), 0)
-                - 1,
-            32
-        );
+        let rules = BagRules::new(&parse_many_bags_data(snippet).unwrap());
+        assert_eq!(rules.count_bags_inside("shiny gold"), Ok(32));
+        assert_eq!(day07_1(snippet), Ok(4));
     }
 }
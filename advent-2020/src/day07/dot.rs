@@ -0,0 +1,152 @@
+/*
+Graphviz export so we can actually look at the rules while debugging, e.g.
+  cargo run -- day07 dot --from "shiny gold" | dot -Tsvg > bags.svg
+An edge A -> B labelled n means "A bags contain n B bags".
+*/
+use super::rules::{BagRules, RuleError};
+
+/// Which part of the rules to draw.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DotScope<'a> {
+    Everything,
+    /// The colour and everything nested inside it (part 2)
+    From(&'a str),
+    /// The colour and everything that can end up holding it (part 1)
+    Into(&'a str),
+}
+
+pub fn to_dot(rules: &BagRules, scope: DotScope) -> Result<String, RuleError> {
+    let (root, mut colors) = match scope {
+        DotScope::Everything => (None, rules.colors().collect()),
+        DotScope::From(color) => (Some(color), rules.nested_in(color)),
+        DotScope::Into(color) => (Some(color), rules.holders_of(color)),
+    };
+    if let Some(color) = root {
+        if rules.id(color).is_none() {
+            return Err(RuleError::UnknownColor(color.to_string()));
+        }
+        // a root that's part of a cycle is nested in (or holds) itself, but only gets drawn once
+        colors.retain(|&c| c != color);
+        colors.insert(0, color);
+    }
+
+    let mut dot = String::from("digraph bags {\n");
+    for &color in &colors {
+        if Some(color) == root {
+            dot.push_str(&format!("    {} [style=bold];\n", quote(color)));
+        } else {
+            dot.push_str(&format!("    {};\n", quote(color)));
+        }
+    }
+    for &color in &colors {
+        for (inner, quantity) in rules.contents(color) {
+            if colors.contains(&inner) {
+                dot.push_str(&format!(
+                    "    {} -> {} [label=\"{}\"];\n",
+                    quote(color),
+                    quote(inner),
+                    quantity
+                ));
+            }
+        }
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+fn quote(color: &str) -> String {
+    format!("\"{}\"", color.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse_many_bags_data;
+    use super::*;
+
+    const SNIPPET: &str = "light red bags contain 1 bright white bag, 2 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+shiny gold bags contain 2 dark olive bags.
+dark olive bags contain no other bags.
+muted yellow bags contain no other bags.";
+
+    #[test]
+    fn it_should_draw_every_rule() {
+        let rules = BagRules::new(&parse_many_bags_data(SNIPPET).unwrap());
+        let expected = r#"digraph bags {
+    "light red";
+    "bright white";
+    "muted yellow";
+    "shiny gold";
+    "dark olive";
+    "light red" -> "bright white" [label="1"];
+    "light red" -> "muted yellow" [label="2"];
+    "bright white" -> "shiny gold" [label="1"];
+    "shiny gold" -> "dark olive" [label="2"];
+}
+"#;
+        assert_eq!(
+            to_dot(&rules, DotScope::Everything),
+            Ok(expected.to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_draw_only_what_is_inside_or_around_a_color() {
+        let rules = BagRules::new(&parse_many_bags_data(SNIPPET).unwrap());
+        let from = r#"digraph bags {
+    "shiny gold" [style=bold];
+    "dark olive";
+    "shiny gold" -> "dark olive" [label="2"];
+}
+"#;
+        assert_eq!(
+            to_dot(&rules, DotScope::From("shiny gold")),
+            Ok(from.to_string())
+        );
+
+        let into = r#"digraph bags {
+    "shiny gold" [style=bold];
+    "bright white";
+    "light red";
+    "bright white" -> "shiny gold" [label="1"];
+    "light red" -> "bright white" [label="1"];
+}
+"#;
+        assert_eq!(
+            to_dot(&rules, DotScope::Into("shiny gold")),
+            Ok(into.to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_draw_a_root_in_a_cycle_once() {
+        let snippet = "shiny gold bags contain 2 dark red bags.
+dark red bags contain 1 shiny gold bag.";
+        let rules = BagRules::new(&parse_many_bags_data(snippet).unwrap());
+        let expected = r#"digraph bags {
+    "shiny gold" [style=bold];
+    "dark red";
+    "shiny gold" -> "dark red" [label="2"];
+    "dark red" -> "shiny gold" [label="1"];
+}
+"#;
+        assert_eq!(
+            to_dot(&rules, DotScope::From("shiny gold")),
+            Ok(expected.to_string())
+        );
+        assert_eq!(
+            to_dot(&rules, DotScope::Into("shiny gold")),
+            Ok(expected.to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_refuse_unknown_colors() {
+        let rules = BagRules::new(&parse_many_bags_data(SNIPPET).unwrap());
+        assert_eq!(
+            to_dot(&rules, DotScope::Into("unreal")),
+            Err(RuleError::UnknownColor("unreal".to_string()))
+        );
+        assert_eq!(quote(r#"odd "quoted" bag"#), r#""odd \"quoted\" bag""#);
+    }
+}
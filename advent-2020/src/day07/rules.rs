@@ -0,0 +1,535 @@
+/*
+The rules are really a weighted graph: an edge from A to B with weight n means "A contains n B bags".
+Colours get turned into small integer ids once (interning), so after that every lookup is an index
+into a Vec instead of a scan over all the bags comparing strings.
+Part 1 walks the edges backwards (who can hold me?), part 2 walks them forwards (what do I hold?).
+*/
+use super::Bag;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+pub type ColorId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Count {
+    NotYet,
+    Counting,
+    Done(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    OnPath,
+    Finished,
+}
+
+#[derive(Debug)]
+pub struct BagRules {
+    colors: Vec<String>,
+    ids: HashMap<String, ColorId>,
+    /// contains[a] lists (b, n) for "a bags contain n b bags"
+    contains: Vec<Vec<(ColorId, u32)>>,
+    /// contained_in[b] lists (a, n) for the same rule, seen from the inside
+    contained_in: Vec<Vec<(ColorId, u32)>>,
+    /// How many rules start with each colour, anything but 1 is a problem
+    definitions: Vec<usize>,
+    /// Bags inside each colour, filled in the first time somebody asks
+    inside_counts: RefCell<Vec<Count>>,
+}
+
+impl BagRules {
+    pub fn new(bags: &[Bag]) -> BagRules {
+        let mut rules = BagRules {
+            colors: Vec::new(),
+            ids: HashMap::new(),
+            contains: Vec::new(),
+            contained_in: Vec::new(),
+            definitions: Vec::new(),
+            inside_counts: RefCell::new(Vec::new()),
+        };
+        for bag in bags {
+            let outer = rules.intern(&bag.color);
+            rules.definitions[outer] += 1;
+            for item in &bag.contains {
+                let inner = rules.intern(&item.color);
+                rules.contains[outer].push((inner, item.quantity));
+                rules.contained_in[inner].push((outer, item.quantity));
+            }
+        }
+        rules.inside_counts = RefCell::new(vec![Count::NotYet; rules.colors.len()]);
+        rules
+    }
+
+    fn intern(&mut self, color: &str) -> ColorId {
+        if let Some(&id) = self.ids.get(color) {
+            return id;
+        }
+        let id = self.colors.len();
+        self.colors.push(color.to_string());
+        self.ids.insert(color.to_string(), id);
+        self.contains.push(Vec::new());
+        self.contained_in.push(Vec::new());
+        self.definitions.push(0);
+        id
+    }
+
+    pub fn id(&self, color: &str) -> Option<ColorId> {
+        self.ids.get(color).copied()
+    }
+
+    pub fn color(&self, id: ColorId) -> &str {
+        &self.colors[id]
+    }
+
+    /// Every colour that shows up anywhere in the rules, in the order it first appeared.
+    pub fn colors(&self) -> impl Iterator<Item = &str> {
+        self.colors.iter().map(String::as_str)
+    }
+
+    /// What a bag of this colour has to hold directly, as (colour, quantity).
+    pub fn contents(&self, color: &str) -> Vec<(&str, u32)> {
+        self.id(color).map_or_else(Vec::new, |id| {
+            self.contains[id]
+                .iter()
+                .map(|&(inner, quantity)| (self.color(inner), quantity))
+                .collect()
+        })
+    }
+
+    /// Colours whose rule directly mentions this colour.
+    pub fn direct_holders_of(&self, color: &str) -> Vec<&str> {
+        self.id(color).map_or_else(Vec::new, |id| {
+            self.contained_in[id]
+                .iter()
+                .map(|&(outer, _)| self.color(outer))
+                .collect()
+        })
+    }
+
+    /// Colours that can end up holding this colour, however deep it's nested.
+    pub fn holders_of(&self, color: &str) -> Vec<&str> {
+        let start = match self.id(color) {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
+        let mut seen = vec![false; self.colors.len()];
+        let mut stack = vec![start];
+        let mut holders = Vec::new();
+        while let Some(id) = stack.pop() {
+            for &(outer, _) in &self.contained_in[id] {
+                if !seen[outer] {
+                    seen[outer] = true;
+                    holders.push(self.color(outer));
+                    stack.push(outer);
+                }
+            }
+        }
+        holders
+    }
+
+    /// Colours that end up inside this colour, however deep they're nested.
+    pub fn nested_in(&self, color: &str) -> Vec<&str> {
+        self.id(color).map_or_else(Vec::new, |id| {
+            self.descendants_of(id)
+                .iter()
+                .enumerate()
+                .filter(|(_, &inside)| inside)
+                .map(|(inner, _)| self.color(inner))
+                .collect()
+        })
+    }
+
+    /// How many bags a bag of this colour holds in total, not counting itself.
+    /// Unknown colours and bags that (eventually) contain themselves are errors, not zero.
+    pub fn count_bags_inside(&self, color: &str) -> Result<u64, RuleError> {
+        let id = self
+            .id(color)
+            .ok_or_else(|| RuleError::UnknownColor(color.to_string()))?;
+        self.count_inside(id)
+    }
+
+    fn count_inside(&self, id: ColorId) -> Result<u64, RuleError> {
+        let state = self.inside_counts.borrow()[id];
+        match state {
+            Count::Done(count) => return Ok(count),
+            // we came back to a bag we're still in the middle of counting
+            Count::Counting => return Err(self.cycle_through(id)),
+            Count::NotYet => (),
+        }
+        self.inside_counts.borrow_mut()[id] = Count::Counting;
+        let mut count: u64 = 0;
+        for &(inner, quantity) in &self.contains[id] {
+            let added = self.count_inside(inner).and_then(|inside| {
+                inside
+                    .checked_add(1)
+                    .and_then(|bags| bags.checked_mul(u64::from(quantity)))
+                    .and_then(|bags| count.checked_add(bags))
+                    .ok_or_else(|| RuleError::TooManyBags(self.color(id).to_string()))
+            });
+            match added {
+                Ok(total) => count = total,
+                Err(e) => {
+                    self.inside_counts.borrow_mut()[id] = Count::NotYet;
+                    return Err(e);
+                }
+            }
+        }
+        self.inside_counts.borrow_mut()[id] = Count::Done(count);
+        Ok(count)
+    }
+
+    /// Every cycle in the rules, each as the colour path that leads back to where it started,
+    /// e.g. `["dark red", "dark orange", "dark red"]`.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut visits = vec![Visit::New; self.colors.len()];
+        let mut path = Vec::new();
+        let mut cycles = Vec::new();
+        for id in 0..self.colors.len() {
+            if visits[id] == Visit::New {
+                self.find_cycles(id, &mut visits, &mut path, &mut cycles);
+            }
+        }
+        cycles
+    }
+
+    fn find_cycles(
+        &self,
+        id: ColorId,
+        visits: &mut Vec<Visit>,
+        path: &mut Vec<ColorId>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        visits[id] = Visit::OnPath;
+        path.push(id);
+        for &(inner, _) in &self.contains[id] {
+            match visits[inner] {
+                Visit::New => self.find_cycles(inner, visits, path, cycles),
+                Visit::OnPath => {
+                    let start = path.iter().position(|&p| p == inner).unwrap_or(0);
+                    let mut cycle: Vec<String> = path[start..]
+                        .iter()
+                        .map(|&p| self.color(p).to_string())
+                        .collect();
+                    cycle.push(self.color(inner).to_string());
+                    cycles.push(cycle);
+                }
+                Visit::Finished => (),
+            }
+        }
+        path.pop();
+        visits[id] = Visit::Finished;
+    }
+
+    fn cycle_through(&self, id: ColorId) -> RuleError {
+        let color = self.color(id);
+        let cycle = self
+            .cycles()
+            .into_iter()
+            .find(|cycle| cycle.iter().any(|c| c == color))
+            .unwrap_or_else(|| vec![color.to_string(), color.to_string()]);
+        RuleError::Cycle(cycle)
+    }
+
+    /// Colours inside `root`, however deep.
+    fn descendants_of(&self, root: ColorId) -> Vec<bool> {
+        let mut seen = vec![false; self.colors.len()];
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            for &(inner, _) in &self.contains[id] {
+                if !seen[inner] {
+                    seen[inner] = true;
+                    stack.push(inner);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Everything that looks off about the rules, seen from the `root` colour we care about.
+    /// Includes bags that are unreachable from `root`, which don't stop us from counting.
+    pub fn diagnose(&self, root: &str) -> Vec<RuleError> {
+        let mut problems = Vec::new();
+        for (id, &definitions) in self.definitions.iter().enumerate() {
+            let color = self.color(id).to_string();
+            if definitions == 0 {
+                problems.push(RuleError::UndefinedColor {
+                    used_by: self
+                        .direct_holders_of(&color)
+                        .into_iter()
+                        .map(str::to_string)
+                        .collect(),
+                    color,
+                });
+            } else if definitions > 1 {
+                problems.push(RuleError::DuplicateDefinition {
+                    color,
+                    times: definitions,
+                });
+            }
+        }
+        problems.extend(self.cycles().into_iter().map(RuleError::Cycle));
+
+        match self.id(root) {
+            None => problems.push(RuleError::UnknownColor(root.to_string())),
+            Some(root_id) => {
+                let mut related = self.descendants_of(root_id);
+                related[root_id] = true;
+                for holder in self.holders_of(root) {
+                    if let Some(id) = self.id(holder) {
+                        related[id] = true;
+                    }
+                }
+                for (id, _) in related.iter().enumerate().filter(|(_, &r)| !r) {
+                    problems.push(RuleError::Unreachable {
+                        color: self.color(id).to_string(),
+                        root: root.to_string(),
+                    });
+                }
+            }
+        }
+        problems
+    }
+
+    /// The problems from `diagnose` that would make the answer wrong; check these before counting.
+    pub fn validate(&self, root: &str) -> Result<(), Vec<RuleError>> {
+        let errors: Vec<RuleError> = self
+            .diagnose(root)
+            .into_iter()
+            .filter(RuleError::is_fatal)
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleError {
+    /// The colour path of a bag that ends up inside itself
+    Cycle(Vec<String>),
+    UndefinedColor {
+        color: String,
+        used_by: Vec<String>,
+    },
+    DuplicateDefinition {
+        color: String,
+        times: usize,
+    },
+    UnknownColor(String),
+    /// Holds more bags than a u64 can count
+    TooManyBags(String),
+    /// Neither inside `root` nor able to hold it, so it can't change either answer
+    Unreachable {
+        color: String,
+        root: String,
+    },
+}
+
+impl RuleError {
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, RuleError::Unreachable { .. })
+    }
+}
+
+impl Display for RuleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleError::Cycle(path) => write!(f, "bags contain themselves: {}", path.join(" -> ")),
+            RuleError::UndefinedColor { color, used_by } => write!(
+                f,
+                "{} bags are used by {} but never defined",
+                color,
+                used_by.join(", ")
+            ),
+            RuleError::DuplicateDefinition { color, times } => {
+                write!(f, "{} bags are defined {} times", color, times)
+            }
+            RuleError::UnknownColor(color) => write!(f, "no rule mentions {} bags", color),
+            RuleError::TooManyBags(color) => {
+                write!(f, "{} bags hold too many bags to count", color)
+            }
+            RuleError::Unreachable { color, root } => write!(
+                f,
+                "{} bags are neither inside nor around {} bags",
+                color, root
+            ),
+        }
+    }
+}
+
+impl Error for RuleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse_many_bags_data;
+    use super::*;
+
+    const EXAMPLE: &str = "light red bags contain 1 bright white bag, 2 muted yellow bags.
+dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+faded blue bags contain no other bags.
+dotted black bags contain no other bags.";
+
+    #[test]
+    fn it_should_intern_every_color_once() {
+        let rules = BagRules::new(&parse_many_bags_data(EXAMPLE).unwrap());
+        assert_eq!(rules.colors().count(), 9);
+        let id = rules.id("shiny gold").unwrap();
+        assert_eq!(rules.color(id), "shiny gold");
+        assert_eq!(rules.id("unreal"), None);
+    }
+
+    #[test]
+    fn it_should_know_contents_in_both_directions() {
+        let rules = BagRules::new(&parse_many_bags_data(EXAMPLE).unwrap());
+        assert_eq!(
+            rules.contents("muted yellow"),
+            vec![("shiny gold", 2), ("faded blue", 9)]
+        );
+        assert!(rules.contents("faded blue").is_empty());
+        assert_eq!(
+            rules.nested_in("shiny gold"),
+            vec!["faded blue", "dark olive", "vibrant plum", "dotted black"]
+        );
+        assert_eq!(
+            rules.direct_holders_of("shiny gold"),
+            vec!["bright white", "muted yellow"]
+        );
+    }
+
+    #[test]
+    fn it_should_count_any_color_and_remember_the_answer() {
+        let rules = BagRules::new(&parse_many_bags_data(EXAMPLE).unwrap());
+        assert_eq!(rules.count_bags_inside("shiny gold"), Ok(32));
+        assert_eq!(rules.count_bags_inside("dark olive"), Ok(7));
+        assert_eq!(rules.count_bags_inside("faded blue"), Ok(0));
+        assert_eq!(
+            rules.count_bags_inside("unreal"),
+            Err(RuleError::UnknownColor("unreal".to_string()))
+        );
+
+        // shiny gold's answer needed vibrant plum on the way, so it should already be cached
+        let vibrant_plum = rules.id("vibrant plum").unwrap();
+        assert_eq!(rules.inside_counts.borrow()[vibrant_plum], Count::Done(11));
+        assert_eq!(rules.count_bags_inside("light red"), Ok(186));
+    }
+
+    #[test]
+    fn the_example_should_be_valid() {
+        let rules = BagRules::new(&parse_many_bags_data(EXAMPLE).unwrap());
+        assert_eq!(rules.validate("shiny gold"), Ok(()));
+        assert!(rules.diagnose("shiny gold").is_empty());
+        assert!(rules.cycles().is_empty());
+    }
+
+    #[test]
+    fn it_should_report_cycles_with_their_path_instead_of_overflowing() {
+        let snippet = "shiny gold bags contain 2 dark red bags.
+dark red bags contain 2 dark orange bags.
+dark orange bags contain 1 dark red bag, 3 faded blue bags.
+faded blue bags contain no other bags.";
+        let rules = BagRules::new(&parse_many_bags_data(snippet).unwrap());
+        let cycle = vec![
+            "dark red".to_string(),
+            "dark orange".to_string(),
+            "dark red".to_string(),
+        ];
+        assert_eq!(rules.cycles(), vec![cycle.clone()]);
+        assert_eq!(
+            rules.validate("shiny gold"),
+            Err(vec![RuleError::Cycle(cycle.clone())])
+        );
+        assert_eq!(
+            rules.count_bags_inside("shiny gold"),
+            Err(RuleError::Cycle(cycle))
+        );
+        // faded blue isn't part of the cycle, so it can still be counted
+        assert_eq!(rules.count_bags_inside("faded blue"), Ok(0));
+    }
+
+    #[test]
+    fn it_should_report_too_many_bags_instead_of_overflowing() {
+        // 4294967295 ^ 3 bags of dotted black is more than a u64 can hold
+        let snippet = "shiny gold bags contain 4294967295 dark red bags.
+dark red bags contain 4294967295 dark orange bags.
+dark orange bags contain 4294967295 dotted black bags.
+dotted black bags contain no other bags.";
+        let rules = BagRules::new(&parse_many_bags_data(snippet).unwrap());
+        assert_eq!(
+            rules.count_bags_inside("dark red"),
+            Ok(4294967295 * 4294967295 + 4294967295)
+        );
+        assert_eq!(
+            rules.count_bags_inside("shiny gold"),
+            Err(RuleError::TooManyBags("shiny gold".to_string()))
+        );
+        assert_eq!(
+            RuleError::TooManyBags("shiny gold".to_string()).to_string(),
+            "shiny gold bags hold too many bags to count"
+        );
+    }
+
+    #[test]
+    fn it_should_report_a_bag_that_holds_itself() {
+        let rules = BagRules::new(
+            &parse_many_bags_data("shiny gold bags contain 1 shiny gold bag.").unwrap(),
+        );
+        assert_eq!(
+            rules.cycles(),
+            vec![vec!["shiny gold".to_string(), "shiny gold".to_string()]]
+        );
+    }
+
+    #[test]
+    fn it_should_report_undefined_duplicate_and_unreachable_bags() {
+        let snippet = "shiny gold bags contain 2 dark red bags.
+light red bags contain 1 shiny gold bag.
+light red bags contain no other bags.
+dotted black bags contain no other bags.";
+        let rules = BagRules::new(&parse_many_bags_data(snippet).unwrap());
+        let problems = rules.diagnose("shiny gold");
+        assert_eq!(
+            problems,
+            vec![
+                RuleError::UndefinedColor {
+                    color: "dark red".to_string(),
+                    used_by: vec!["shiny gold".to_string()],
+                },
+                RuleError::DuplicateDefinition {
+                    color: "light red".to_string(),
+                    times: 2,
+                },
+                RuleError::Unreachable {
+                    color: "dotted black".to_string(),
+                    root: "shiny gold".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            rules.validate("shiny gold"),
+            Err(problems[..2].to_vec()),
+            "unreachable bags are only a warning"
+        );
+        assert_eq!(
+            problems[0].to_string(),
+            "dark red bags are used by shiny gold but never defined"
+        );
+        assert_eq!(
+            rules.validate("unreal"),
+            Err(vec![
+                problems[0].clone(),
+                problems[1].clone(),
+                RuleError::UnknownColor("unreal".to_string())
+            ])
+        );
+    }
+}
@@ -1,136 +1,120 @@
-use std::collections::HashSet;
-use std::convert::TryInto;
-use std::ops::Index;
-
-pub fn day08_1(input: &str) -> i32 {
-    return_accumulator(input)
+mod asm;
+mod debugger;
+mod opcode;
+mod repair;
+mod vm;
+
+use debugger::Debugger;
+use opcode::{OpcodeSet, VmError};
+use repair::RepairError;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use vm::{parse_program, Instruction, ParseInstructionError, State, Vm};
+
+pub fn day08_1(input: &str) -> Result<i64, ConsoleError> {
+    return_accumulator(&parse_program(input)?)
 }
 
-pub fn day08_2(input: &str) -> i32 {
+pub fn day08_2(input: &str) -> Result<i64, ConsoleError> {
     /*
     Naive implementation says go through and find all the indexes
     where jmp and nop appear.
     For each of those indexes, make the switch and run the whole program again
+    (the repair module does it in linear time instead)
     */
-    swap_doo(input)
+    Ok(repair::repair(&parse_program(input)?)?.acc)
 }
 
-fn split_operation_argument(input: &str) -> [String; 2] {
-    match input
-        .trim()
-        .split_whitespace()
-        .map(str::to_owned)
-        .collect::<Vec<_>>()
-        .try_into()
-    {
-        Ok(arr) => arr,
-        Err(_) => [String::new(), String::new()],
-    }
+/// Anything that stops the console from giving an answer
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleError {
+    Parse(ParseInstructionError),
+    /// The instruction at `pc` couldn't run
+    Faulted {
+        pc: usize,
+        error: VmError,
+    },
+    Repair(RepairError),
 }
 
-fn create_all_operations(input: &str) -> Vec<[String; 2]> {
-    input.split('\n').map(split_operation_argument).collect()
+impl From<ParseInstructionError> for ConsoleError {
+    fn from(e: ParseInstructionError) -> Self {
+        ConsoleError::Parse(e)
+    }
 }
 
-// We probably want to store the index, and what to know when we've added an index that we've already added previously
-// It sounds like the use case for a set, but is there a data structure that tells you when you've added a duplicate?
-// HashSet.insert can do this; returns true if did not have value; false if it did
-
-fn return_accumulator(input: &str) -> i32 {
-    let mut acc = 0;
-    let mut idx: usize = 0;
-    let mut set: HashSet<usize> = HashSet::new();
-    let instructions = create_all_operations(input);
-    // We don't need to iterate everything, we just need to give it a start until we get false
-    while set.insert(idx) && idx < instructions.len() {
-        let [operation, argument] = instructions.index(idx);
-        hozier_move(operation, argument, &mut idx, &mut acc);
+impl From<RepairError> for ConsoleError {
+    fn from(e: RepairError) -> Self {
+        ConsoleError::Repair(e)
     }
-    acc
 }
 
-// Worth re-reading in the future: https://www.snoyman.com/blog/2020/05/no-mutable-parameters-in-rust/
-fn hozier_move(op: &str, argument: &str, idx: &mut usize, acc: &mut i32) {
-    match op {
-        "acc" => {
-            *acc += argument
-                .parse::<i32>()
-                .expect("Argument should be parseable as a signed integer");
-            *idx += 1;
-        }
-        "jmp" => {
-            let movement = argument
-                .parse::<i32>()
-                .expect("Argument should be parseable as a signed integer");
-
-            if movement.is_negative() {
-                *idx -= movement.abs() as usize
-            } else {
-                *idx += movement.abs() as usize;
-            }
-        }
-        "nop" => {
-            *idx += 1;
-        }
-        _ => {
-            panic!("Shouldn't have made it here");
+impl Display for ConsoleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsoleError::Parse(e) => write!(f, "{}", e),
+            ConsoleError::Faulted { pc, error } => write!(f, "pc {}: {}", pc, error),
+            ConsoleError::Repair(e) => write!(f, "{}", e),
         }
     }
 }
 
-fn swap_doo(input: &str) -> i32 {
-    // looking for index one-less than len()
-    // find all of jmp and nop (maybe easier to just find non-acc)
-    // for each of those indexes, take the instructions and for the special index switch what you do
-    let mut acc = 0;
-    let mut idx: usize = 0;
-    let mut set: HashSet<usize> = HashSet::new();
-    let instructions = create_all_operations(input);
-    let non_acc: Vec<usize> = instructions
-        .iter()
-        .enumerate()
-        .filter(|(_, it)| it[0] != "acc")
-        .map(|x| x.0)
-        .collect();
-
-    for change in non_acc {
-        while set.insert(idx) && idx < instructions.len() {
-            let [operation, argument] = instructions.index(idx);
-            if idx == change {
-                if operation == "nop" {
-                    hozier_move("jmp", argument, &mut idx, &mut acc);
-                } else {
-                    hozier_move("nop", argument, &mut idx, &mut acc);
-                }
-            } else {
-                hozier_move(operation.as_str(), argument, &mut idx, &mut acc);
-            }
-        }
-        if idx == instructions.len() {
-            break;
-        } else {
-            acc = 0;
-            idx = 0;
-            set.clear();
-        }
+impl Error for ConsoleError {}
+
+/// Assembles a program written with labels and comments into the puzzle format.
+pub fn day08_assemble(source: &str) -> Result<String, ParseInstructionError> {
+    asm::assemble(source)
+}
+
+/// The program annotated with basic blocks, jump targets and loops.
+pub fn day08_disassemble(input: &str) -> Result<String, ParseInstructionError> {
+    Ok(asm::disassemble(&parse_program(input)?))
+}
+
+/// Which instruction has to flip for the program to halt, and the accumulator once it does.
+pub fn day08_repair(input: &str) -> Result<String, ParseInstructionError> {
+    Ok(match repair::repair(&parse_program(input)?) {
+        Ok(repair) => repair.to_string(),
+        Err(e) => e.to_string(),
+    })
+}
+
+/// Every instruction the unpatched program runs before it stops, one per line.
+pub fn day08_trace(input: &str) -> Result<String, ParseInstructionError> {
+    let (state, trace) = debugger::trace(parse_program(input)?);
+    let mut out: String = trace.iter().map(|entry| format!("{}\n", entry)).collect();
+    out.push_str(&format!("{}\n", state));
+    Ok(out)
+}
+
+/// Step debugger reading commands from stdin, `help` lists them. The program may use the
+/// extended opcodes, so it can be patched with them too.
+pub fn day08_debug(input: &str) -> Result<(), Box<dyn Error>> {
+    let opcodes = OpcodeSet::extended();
+    let program = opcodes.parse_program(input)?;
+    let mut debugger = Debugger::new(Vm::with_opcodes(opcodes, program));
+    debugger::repl(&mut debugger, io::stdin().lock(), io::stdout())?;
+    Ok(())
+}
+
+// We probably want to store the index, and what to know when we've added an index that we've already added previously
+// It sounds like the use case for a set, but is there a data structure that tells you when you've added a duplicate?
+// HashSet.insert can do this; returns true if did not have value; false if it did
+// (The Vm keeps a visited list now, so it knows to stop before running anything twice)
+fn return_accumulator(program: &[Instruction]) -> Result<i64, ConsoleError> {
+    let mut vm = Vm::new(program.to_vec());
+    match vm.run() {
+        State::Faulted { pc, error } => Err(ConsoleError::Faulted { pc, error }),
+        _ => Ok(vm.acc()),
     }
-    acc
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_example_program_execution() {
-        /*
-        acc increases or decreases a single global value called the accumulator by the value given in the argument
-        jmp jumps to a new instruction relative to itself. The next instruction to execute is found using the argument as an offset from the jmp instruction
-        nop stands for No OPeration; on to the next
-
-        Immediately before any instruction is executed a second time, what value is in the accumulator?
-        */
-        let input = r"nop +0
+    const EXAMPLE: &str = r"nop +0
         acc +1
         jmp +4
         acc +3
@@ -140,44 +124,37 @@ mod tests {
         jmp -4
         acc +6";
 
-        let actual = return_accumulator(input);
-        assert_eq!(actual, 5);
+    #[test]
+    fn test_example_program_execution() {
+        /*
+        acc increases or decreases a single global value called the accumulator by the value given in the argument
+        jmp jumps to a new instruction relative to itself. The next instruction to execute is found using the argument as an offset from the jmp instruction
+        nop stands for No OPeration; on to the next
+
+        Immediately before any instruction is executed a second time, what value is in the accumulator?
+        */
+        assert_eq!(day08_1(EXAMPLE), Ok(5));
     }
 
     #[test]
     fn test_operation_arg_split() {
         let input = "nop +0";
-        let expected = [String::from("nop"), String::from("+0")];
-        assert_eq!(split_operation_argument(input), expected)
+        let instruction: Instruction = input.parse().unwrap();
+        assert_eq!(instruction.opcode(), "nop");
+        assert_eq!(instruction.operands(), &[opcode::Operand::Value(0)]);
     }
 
     #[test]
     fn test_create_all_operations() {
-        let input = r"nop +0
-        acc +1
-        jmp +4
-        acc +3
-        jmp -3
-        acc -99
-        acc +1
-        jmp -4
-        acc +6";
-
         let expected = vec![
-            [String::from("nop"), "+0".to_string()],
-            ["acc".to_string(), "+1".to_string()],
-            ["jmp".to_string(), "+4".to_string()],
-            ["acc".to_string(), "+3".to_string()],
-            ["jmp".to_string(), "-3".to_string()],
-            ["acc".to_string(), "-99".to_string()],
-            ["acc".to_string(), "+1".to_string()],
-            ["jmp".to_string(), "-4".to_string()],
-            ["acc".to_string(), "+6".to_string()],
+            "nop +0", "acc +1", "jmp +4", "acc +3", "jmp -3", "acc -99", "acc +1", "jmp -4",
+            "acc +6",
         ];
 
-        let actual = create_all_operations(input);
+        let actual = parse_program(EXAMPLE).unwrap();
 
         assert_eq!(actual.len(), 9);
+        let actual: Vec<String> = actual.iter().map(Instruction::to_string).collect();
         assert_eq!(actual, expected);
     }
 
@@ -192,17 +169,29 @@ mod tests {
 
     #[test]
     fn test_part_02() {
-        let input = r"nop +0
-        acc +1
-        jmp +4
-        acc +3
-        jmp -3
-        acc -99
-        acc +1
-        jmp -4
-        acc +6";
+        assert_eq!(day08_2(EXAMPLE), Ok(8));
+    }
 
-        let actual = swap_doo(input);
-        assert_eq!(actual, 8);
+    #[test]
+    fn test_part_02_needs_a_broken_program() {
+        assert_eq!(
+            day08_2("nop +0\nacc +1"),
+            Err(ConsoleError::Repair(RepairError::AlreadyHalts))
+        );
+        assert_eq!(
+            day08_repair("nop +0\nacc +1"),
+            Ok("the program already halts without a flip".to_string())
+        );
+    }
+
+    #[test]
+    fn test_accumulator_overflow() {
+        assert_eq!(
+            day08_1("acc +9223372036854775807\nacc +1"),
+            Err(ConsoleError::Faulted {
+                pc: 1,
+                error: VmError::Overflow
+            })
+        );
     }
 }
@@ -0,0 +1,287 @@
+/*
+Writing console programs by hand. The assembler takes the puzzle format plus:
+- comments, from `;` or `#` to the end of the line
+- labels, `name:` on their own line or in front of an instruction
+- a label instead of a number, `jmp loop`, which becomes the relative offset to that label
+and prints plain `op +n` lines back out.
+
+The disassembler goes the other way and annotates a program with its basic blocks, where each
+jmp goes, which instructions are jumped to, and which instructions sit on a loop. The
+annotations are all comments, so its output assembles back into the same program.
+*/
+use super::opcode::FlowKind;
+use super::repair::successor;
+use super::vm::{Instruction, ParseInstructionError};
+use std::collections::HashMap;
+
+struct Line<'a> {
+    number: usize,
+    op: &'a str,
+    argument: &'a str,
+}
+
+fn is_label(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Assembles `source` into one `op +n` instruction per line.
+pub fn assemble(source: &str) -> Result<String, ParseInstructionError> {
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut lines = Vec::new();
+
+    for (idx, text) in source.lines().enumerate() {
+        let number = idx + 1;
+        let mut text = text.split([';', '#']).next().unwrap().trim();
+        while let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if !is_label(label) {
+                return Err(ParseInstructionError::new(
+                    number,
+                    format!("invalid label: {}", label),
+                ));
+            }
+            if labels.insert(label, lines.len()).is_some() {
+                return Err(ParseInstructionError::new(
+                    number,
+                    format!("label defined twice: {}", label),
+                ));
+            }
+            text = rest.trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+        let mut words = text.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some(op), Some(argument), None) => lines.push(Line {
+                number,
+                op,
+                argument,
+            }),
+            _ => {
+                return Err(ParseInstructionError::new(
+                    number,
+                    format!("expected `op +n` or `op label`, found: {}", text),
+                ))
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let argument = match line.argument.parse::<i64>() {
+            Ok(n) => n,
+            Err(_) if line.op == "acc" => {
+                return Err(ParseInstructionError::new(
+                    line.number,
+                    format!("acc needs a number, found: {}", line.argument),
+                ))
+            }
+            Err(_) => match labels.get(line.argument) {
+                Some(&target) => target as i64 - index as i64,
+                None => {
+                    return Err(ParseInstructionError::new(
+                        line.number,
+                        format!("unknown label: {}", line.argument),
+                    ))
+                }
+            },
+        };
+        let instruction = format!("{} {:+}", line.op, argument)
+            .parse::<Instruction>()
+            .map_err(|_| {
+                ParseInstructionError::new(line.number, format!("unknown operation: {}", line.op))
+            })?;
+        out.push_str(&format!("{}\n", instruction));
+    }
+    Ok(out)
+}
+
+/// For every instruction, which loop it's part of (loops are numbered in program order).
+fn loops(program: &[Instruction]) -> Vec<Option<usize>> {
+    const NEW: u8 = 0;
+    const ON_PATH: u8 = 1;
+    const DONE: u8 = 2;
+
+    let len = program.len();
+    let mut color = vec![NEW; len];
+    let mut membership = vec![None; len];
+    let mut count = 0;
+    for start in 0..len {
+        let mut path = Vec::new();
+        let mut pc = Some(start);
+        while let Some(at) = pc.filter(|&at| at < len && color[at] == NEW) {
+            color[at] = ON_PATH;
+            path.push(at);
+            pc = successor(at, &program[at], len);
+        }
+        if let Some(at) = pc.filter(|&at| at < len && color[at] == ON_PATH) {
+            let from = path.iter().position(|&p| p == at).unwrap();
+            for &p in &path[from..] {
+                membership[p] = Some(count);
+            }
+            count += 1;
+        }
+        for p in path {
+            color[p] = DONE;
+        }
+    }
+    membership
+}
+
+/// Lists the program with its annotations as comments.
+pub fn disassemble(program: &[Instruction]) -> String {
+    let len = program.len();
+    let mut sources = vec![Vec::new(); len];
+    let mut leaders = vec![false; len];
+    if len > 0 {
+        leaders[0] = true;
+    }
+    for (pc, instruction) in program.iter().enumerate() {
+        if instruction.flow_kind() == FlowKind::Jump {
+            if let Some(target) = successor(pc, instruction, len).filter(|&t| t < len) {
+                sources[target].push(pc);
+                leaders[target] = true;
+            }
+            if pc + 1 < len {
+                leaders[pc + 1] = true;
+            }
+        }
+    }
+    let loops = loops(program);
+
+    let mut out = String::new();
+    let mut block = 0;
+    for (pc, instruction) in program.iter().enumerate() {
+        if leaders[pc] {
+            out.push_str(&format!("; block {}\n", block));
+            block += 1;
+        }
+        let mut notes = vec![format!("pc {}", pc)];
+        if instruction.flow_kind() == FlowKind::Jump {
+            notes.push(match successor(pc, instruction, len) {
+                Some(target) if target == len => "jumps to the end".to_string(),
+                Some(target) => format!("jumps to {}", target),
+                None => format!("jumps out of bounds ({})", instruction.next_pc(pc)),
+            });
+        }
+        if !sources[pc].is_empty() {
+            let from: Vec<String> = sources[pc].iter().map(usize::to_string).collect();
+            notes.push(format!("target of {}", from.join(", ")));
+        }
+        if let Some(id) = loops[pc] {
+            notes.push(format!("in loop {}", id));
+        }
+        out.push_str(&format!(
+            "    {:<9} ; {}\n",
+            instruction.to_string(),
+            notes.join(", ")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day08::vm::parse_program;
+
+    const EXAMPLE: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+    #[test]
+    fn it_should_resolve_labels_to_relative_jumps() {
+        let source = "
+            ; a loop, and a way around it
+            start:  nop done    # a nop keeps its offset, flip it and it jumps
+                    acc +1
+            again:
+                    acc +1
+                    jmp again
+            done:   jmp end
+                    jmp start
+            end:
+        ";
+        assert_eq!(
+            assemble(source).unwrap(),
+            "nop +4\nacc +1\nacc +1\njmp -1\njmp +2\njmp -5\n"
+        );
+    }
+
+    #[test]
+    fn it_should_report_assembler_errors_by_line() {
+        let error = |source| assemble(source).unwrap_err().to_string();
+        assert_eq!(error("jmp nowhere"), "line 1: unknown label: nowhere");
+        assert_eq!(error("a:\na: nop +0"), "line 2: label defined twice: a");
+        assert_eq!(error("x:\nacc x"), "line 2: acc needs a number, found: x");
+        assert_eq!(error("nop +0\nmul +2"), "line 2: unknown operation: mul");
+        assert_eq!(error("1st: nop +0"), "line 1: invalid label: 1st");
+        assert_eq!(
+            error("jmp"),
+            "line 1: expected `op +n` or `op label`, found: jmp"
+        );
+    }
+
+    #[test]
+    fn it_should_find_loops() {
+        let program = parse_program(EXAMPLE).unwrap();
+        let expected = vec![
+            None,
+            Some(0),
+            Some(0),
+            Some(0),
+            Some(0),
+            None,
+            Some(0),
+            Some(0),
+            None,
+        ];
+        assert_eq!(loops(&program), expected);
+        let program = parse_program("jmp +0\nnop +0\njmp -1").unwrap();
+        assert_eq!(loops(&program), vec![Some(0), Some(1), Some(1)]);
+    }
+
+    #[test]
+    fn it_should_annotate_the_example() {
+        let program = parse_program(EXAMPLE).unwrap();
+        assert_eq!(
+            disassemble(&program),
+            "; block 0
+    nop +0    ; pc 0
+; block 1
+    acc +1    ; pc 1, target of 4, in loop 0
+    jmp +4    ; pc 2, jumps to 6, in loop 0
+; block 2
+    acc +3    ; pc 3, target of 7, in loop 0
+    jmp -3    ; pc 4, jumps to 1, in loop 0
+; block 3
+    acc -99   ; pc 5
+; block 4
+    acc +1    ; pc 6, target of 2, in loop 0
+    jmp -4    ; pc 7, jumps to 3, in loop 0
+; block 5
+    acc +6    ; pc 8
+"
+        );
+    }
+
+    #[test]
+    fn disassembled_programs_should_assemble_back() {
+        let program = parse_program(EXAMPLE).unwrap();
+        assert_eq!(
+            assemble(&disassemble(&program)).unwrap(),
+            format!("{}\n", EXAMPLE)
+        );
+    }
+}
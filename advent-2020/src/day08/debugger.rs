@@ -0,0 +1,511 @@
+/*
+Debugging the console by hand. The Debugger wraps a Vm and records a trace entry for every
+instruction that runs, stops on breakpoints (a pc or an opcode), and can patch instructions
+between steps with anything the Vm's opcodes decode. `repl` drives it with one command per line, so it works with stdin or a test string.
+*/
+use super::vm::{Instruction, State, Vm};
+use std::fmt::{Display, Formatter};
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+/// One executed instruction, `acc` is the accumulator after it ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: usize,
+    pub instruction: Instruction,
+    pub acc: i64,
+}
+
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:>4}  {}  acc {}", self.pc, self.instruction, self.acc)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    Pc(usize),
+    Opcode(String),
+}
+
+impl Breakpoint {
+    fn hits(&self, pc: usize, instruction: &Instruction) -> bool {
+        match self {
+            Breakpoint::Pc(at) => *at == pc,
+            Breakpoint::Opcode(op) => op == instruction.opcode(),
+        }
+    }
+}
+
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(pc) = s.parse() {
+            return Ok(Breakpoint::Pc(pc));
+        }
+        // the debugger checks the opcode against the Vm's opcodes when it's added
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_lowercase()) {
+            Ok(Breakpoint::Opcode(s.to_string()))
+        } else {
+            Err(format!("expected a pc or an opcode, found: {}", s))
+        }
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Pc(pc) => write!(f, "pc {}", pc),
+            Breakpoint::Opcode(op) => write!(f, "opcode {}", op),
+        }
+    }
+}
+
+/// Why `resume` gave control back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// The instruction at `pc` has a breakpoint and hasn't run yet
+    Breakpoint {
+        pc: usize,
+    },
+    Finished(State),
+}
+
+#[derive(Debug)]
+pub struct Debugger {
+    vm: Vm,
+    breakpoints: Vec<Breakpoint>,
+    trace: Vec<TraceEntry>,
+}
+
+impl Debugger {
+    pub fn new(vm: Vm) -> Debugger {
+        Debugger {
+            vm,
+            breakpoints: Vec::new(),
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn vm(&self) -> &Vm {
+        &self.vm
+    }
+
+    pub fn trace(&self) -> &[TraceEntry] {
+        &self.trace
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> Result<(), String> {
+        if let Breakpoint::Opcode(op) = &breakpoint {
+            if self.vm.opcodes().get(op).is_none() {
+                return Err(format!("unknown operation: {}", op));
+            }
+        }
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+        Ok(())
+    }
+
+    /// Returns false if there was no such breakpoint.
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        let before = self.breakpoints.len();
+        self.breakpoints.retain(|b| b != breakpoint);
+        self.breakpoints.len() != before
+    }
+
+    /// Runs one instruction and records it in the trace.
+    pub fn step(&mut self) -> State {
+        let pc = self.vm.pc();
+        let instruction = match self.vm.current() {
+            Some(instruction) => instruction.clone(),
+            None => return self.vm.state(),
+        };
+        let state = self.vm.step();
+        // a detected loop or a fault means the instruction was refused, not run
+        if !matches!(state, State::LoopDetected { .. } | State::Faulted { .. }) {
+            self.trace.push(TraceEntry {
+                pc,
+                instruction,
+                acc: self.vm.acc(),
+            });
+        }
+        state
+    }
+
+    /// Runs until a breakpoint or the end. Always runs at least one instruction, so resuming
+    /// from a breakpoint doesn't stop on it again straight away.
+    pub fn resume(&mut self) -> Stop {
+        loop {
+            let state = self.step();
+            if state != State::Running {
+                return Stop::Finished(state);
+            }
+            if let Some(instruction) = self.vm.current() {
+                let pc = self.vm.pc();
+                if self.breakpoints.iter().any(|b| b.hits(pc, instruction)) {
+                    return Stop::Breakpoint { pc };
+                }
+            }
+        }
+    }
+
+    /// Replaces the instruction at `index`, keeping its operands unless new ones are given.
+    /// Returns the old and new instruction.
+    pub fn patch(
+        &mut self,
+        index: usize,
+        opcode: &str,
+        operands: &[String],
+    ) -> Result<(Instruction, Instruction), String> {
+        let old = self
+            .vm
+            .program()
+            .get(index)
+            .ok_or_else(|| format!("no instruction at {}", index))?
+            .clone();
+        let operands = if operands.is_empty() {
+            old.operands().iter().map(|o| o.to_string()).collect()
+        } else {
+            operands.to_vec()
+        };
+        let new = self
+            .vm
+            .opcodes()
+            .decode(&format!("{} {}", opcode, operands.join(" ")))?;
+        self.vm.patch(index, new.clone());
+        Ok((old, new))
+    }
+
+    /// Back to the start with an empty trace. Patches and breakpoints stay.
+    pub fn reset(&mut self) {
+        self.vm.reset();
+        self.trace.clear();
+    }
+}
+
+/// Runs the whole program and hands back every instruction that ran.
+pub fn trace(program: Vec<Instruction>) -> (State, Vec<TraceEntry>) {
+    let mut debugger = Debugger::new(Vm::new(program));
+    while debugger.step() == State::Running {}
+    (debugger.vm.state(), debugger.trace)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inspect {
+    Acc,
+    Pc,
+    State,
+    Trace,
+    Output,
+    Register(char),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Step(usize),
+    Continue,
+    Print(Inspect),
+    Patch {
+        index: usize,
+        opcode: String,
+        operands: Vec<String>,
+    },
+    Loops(bool),
+    Break(Breakpoint),
+    Delete(Breakpoint),
+    Reset,
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let number = |word: &str| {
+            word.parse::<usize>()
+                .map_err(|_| format!("expected a number, found: {}", word))
+        };
+        match words.as_slice() {
+            ["step"] | ["s"] => Ok(Command::Step(1)),
+            ["step", n] | ["s", n] => Ok(Command::Step(number(n)?)),
+            ["continue"] | ["c"] => Ok(Command::Continue),
+            ["print", "acc"] => Ok(Command::Print(Inspect::Acc)),
+            ["print", "pc"] => Ok(Command::Print(Inspect::Pc)),
+            ["print", "state"] => Ok(Command::Print(Inspect::State)),
+            ["print", "trace"] => Ok(Command::Print(Inspect::Trace)),
+            ["print", "out"] => Ok(Command::Print(Inspect::Output)),
+            ["print", register] if register.chars().count() == 1 => Ok(Command::Print(
+                Inspect::Register(register.chars().next().unwrap()),
+            )),
+            ["patch", index, opcode, operands @ ..] => Ok(Command::Patch {
+                index: number(index)?,
+                opcode: opcode.to_string(),
+                operands: operands.iter().map(|o| o.to_string()).collect(),
+            }),
+            ["loops", "on"] => Ok(Command::Loops(true)),
+            ["loops", "off"] => Ok(Command::Loops(false)),
+            ["break", at] => Ok(Command::Break(at.parse()?)),
+            ["delete", at] => Ok(Command::Delete(at.parse()?)),
+            ["reset"] => Ok(Command::Reset),
+            ["help"] => Ok(Command::Help),
+            ["quit"] | ["q"] => Ok(Command::Quit),
+            _ => Err(format!("unknown command: {} (try `help`)", s.trim())),
+        }
+    }
+}
+
+const HELP: &str = "\
+step [N]              run one (or N) instructions
+continue              run until a breakpoint or the end
+print acc|pc|state|trace|out|REGISTER
+patch I OP [ARG...]   replace instruction I, keeping its operands unless new ones are given
+loops on|off          stop before running an instruction twice (on to begin with)
+break PC|OP           stop before running that pc or opcode
+delete PC|OP          remove a breakpoint
+reset                 start over, keeping patches and breakpoints
+quit";
+
+/// Reads commands from `input` until it runs out or `quit`, writing results to `output`.
+/// Bad commands are reported and skipped.
+pub fn repl<R: BufRead, W: Write>(
+    debugger: &mut Debugger,
+    input: R,
+    mut output: W,
+) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let command = match line.parse::<Command>() {
+            Ok(command) => command,
+            Err(e) => {
+                writeln!(output, "error: {}", e)?;
+                continue;
+            }
+        };
+        match command {
+            Command::Step(n) => {
+                for _ in 0..n {
+                    let ran = debugger.trace().len();
+                    let state = debugger.step();
+                    if let Some(entry) = debugger.trace().get(ran) {
+                        writeln!(output, "{}", entry)?;
+                    }
+                    if state != State::Running {
+                        writeln!(output, "{}, acc {}", state, debugger.vm().acc())?;
+                        break;
+                    }
+                }
+            }
+            Command::Continue => match debugger.resume() {
+                Stop::Breakpoint { pc } => {
+                    let instruction = &debugger.vm().program()[pc];
+                    writeln!(output, "breakpoint at pc {}: {}", pc, instruction)?;
+                }
+                Stop::Finished(state) => {
+                    writeln!(output, "{}, acc {}", state, debugger.vm().acc())?
+                }
+            },
+            Command::Print(Inspect::Acc) => writeln!(output, "acc {}", debugger.vm().acc())?,
+            Command::Print(Inspect::Pc) => writeln!(output, "pc {}", debugger.vm().pc())?,
+            Command::Print(Inspect::State) => writeln!(output, "{}", debugger.vm().state())?,
+            Command::Print(Inspect::Trace) => {
+                for entry in debugger.trace() {
+                    writeln!(output, "{}", entry)?;
+                }
+            }
+            Command::Print(Inspect::Output) => {
+                let out: Vec<String> = debugger
+                    .vm()
+                    .cpu()
+                    .output()
+                    .iter()
+                    .map(i64::to_string)
+                    .collect();
+                writeln!(output, "out {}", out.join(", "))?
+            }
+            Command::Print(Inspect::Register(name)) => match debugger.vm().cpu().register(name) {
+                Ok(value) => writeln!(output, "{} {}", name, value)?,
+                Err(e) => writeln!(output, "error: {}", e)?,
+            },
+            Command::Patch {
+                index,
+                opcode,
+                operands,
+            } => match debugger.patch(index, &opcode, &operands) {
+                Ok((old, new)) => writeln!(output, "patched {}: {} -> {}", index, old, new)?,
+                Err(e) => writeln!(output, "error: {}", e)?,
+            },
+            Command::Loops(on) => {
+                debugger.vm.set_loop_detection(on);
+                writeln!(output, "loop detection {}", if on { "on" } else { "off" })?;
+            }
+            Command::Break(breakpoint) => match debugger.add_breakpoint(breakpoint.clone()) {
+                Ok(()) => writeln!(output, "breakpoint set: {}", breakpoint)?,
+                Err(e) => writeln!(output, "error: {}", e)?,
+            },
+            Command::Delete(breakpoint) => {
+                if debugger.remove_breakpoint(&breakpoint) {
+                    writeln!(output, "breakpoint deleted: {}", breakpoint)?;
+                } else {
+                    writeln!(output, "error: no breakpoint on {}", breakpoint)?;
+                }
+            }
+            Command::Reset => debugger.reset(),
+            Command::Help => writeln!(output, "{}", HELP)?,
+            Command::Quit => break,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day08::opcode::OpcodeSet;
+    use crate::day08::vm::parse_program;
+
+    const EXAMPLE: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+    fn debugger() -> Debugger {
+        Debugger::new(Vm::new(parse_program(EXAMPLE).unwrap()))
+    }
+
+    fn session(debugger: &mut Debugger, commands: &str) -> String {
+        let mut output = Vec::new();
+        repl(debugger, commands.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn it_should_trace_every_instruction_that_ran() {
+        let (state, trace) = trace(parse_program(EXAMPLE).unwrap());
+        assert_eq!(state, State::LoopDetected { pc: 1 });
+        let pcs: Vec<usize> = trace.iter().map(|entry| entry.pc).collect();
+        assert_eq!(pcs, vec![0, 1, 2, 6, 7, 3, 4]);
+        assert_eq!(trace.last().unwrap().acc, 5);
+        assert_eq!(trace[3].to_string(), "   6  acc +1  acc 2");
+    }
+
+    #[test]
+    fn it_should_stop_on_breakpoints() {
+        let mut debugger = debugger();
+        debugger.add_breakpoint(Breakpoint::Pc(3)).unwrap();
+        debugger.add_breakpoint("jmp".parse().unwrap()).unwrap();
+        assert_eq!(debugger.resume(), Stop::Breakpoint { pc: 2 });
+        assert_eq!(debugger.resume(), Stop::Breakpoint { pc: 7 });
+        assert_eq!(debugger.resume(), Stop::Breakpoint { pc: 3 });
+        assert_eq!(debugger.resume(), Stop::Breakpoint { pc: 4 });
+        assert_eq!(
+            debugger.resume(),
+            Stop::Finished(State::LoopDetected { pc: 1 })
+        );
+        assert!(debugger.remove_breakpoint(&Breakpoint::Pc(3)));
+        assert!(!debugger.remove_breakpoint(&Breakpoint::Pc(3)));
+        assert_eq!(
+            debugger.add_breakpoint("mul".parse().unwrap()),
+            Err("unknown operation: mul".to_string())
+        );
+        assert!("JMP".parse::<Breakpoint>().is_err());
+    }
+
+    #[test]
+    fn it_should_parse_commands() {
+        assert_eq!("step".parse(), Ok(Command::Step(1)));
+        assert_eq!("s 3".parse(), Ok(Command::Step(3)));
+        assert_eq!("print acc".parse(), Ok(Command::Print(Inspect::Acc)));
+        assert_eq!(
+            "patch 12 nop".parse(),
+            Ok(Command::Patch {
+                index: 12,
+                opcode: "nop".to_string(),
+                operands: Vec::new()
+            })
+        );
+        assert_eq!("break 4".parse(), Ok(Command::Break(Breakpoint::Pc(4))));
+        assert_eq!(
+            "print b".parse(),
+            Ok(Command::Print(Inspect::Register('b')))
+        );
+        assert_eq!("loops off".parse(), Ok(Command::Loops(false)));
+        assert!("print everything".parse::<Command>().is_err());
+        assert!("step many".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn it_should_debug_a_program_by_hand() {
+        let mut debugger = debugger();
+        let output = session(
+            &mut debugger,
+            "step 2\nprint acc\nbreak 7\ncontinue\npatch 7 nop\ncontinue\nprint acc\nquit\nstep",
+        );
+        assert_eq!(
+            output,
+            "   0  nop +0  acc 0
+   1  acc +1  acc 1
+acc 1
+breakpoint set: pc 7
+breakpoint at pc 7: jmp -4
+patched 7: jmp -4 -> nop -4
+halted, acc 8
+acc 8
+"
+        );
+    }
+
+    #[test]
+    fn it_should_debug_extended_programs() {
+        let opcodes = OpcodeSet::extended();
+        let program = opcodes
+            .parse_program("cpy 2 b\nout b\nadd -1 b\njnz b -2")
+            .unwrap();
+        let mut debugger = Debugger::new(Vm::with_opcodes(opcodes, program));
+        let output = session(
+            &mut debugger,
+            "continue\nloops off\nreset\nbreak out\ncontinue\ncontinue\nprint out\nprint b\n\
+             patch 3 jnz a -2\ncontinue\nprint out\nprint B",
+        );
+        assert_eq!(
+            output,
+            "loop detected at pc 1, acc 0
+loop detection off
+breakpoint set: opcode out
+breakpoint at pc 1: out b
+breakpoint at pc 1: out b
+out 2
+b 1
+patched 3: jnz b -2 -> jnz a -2
+halted, acc 0
+out 2, 1
+error: no register B
+"
+        );
+    }
+
+    #[test]
+    fn it_should_report_bad_commands_and_keep_going() {
+        let mut debugger = debugger();
+        let output = session(&mut debugger, "jump\npatch 40 nop\npatch 0 mul\nprint pc");
+        assert_eq!(
+            output,
+            "error: unknown command: jump (try `help`)
+error: no instruction at 40
+error: unknown operation: mul
+pc 0
+"
+        );
+    }
+}
@@ -0,0 +1,547 @@
+/*
+The opcodes aren't baked into the Vm. Each opcode is an `Opcode` trait object in an `OpcodeSet`,
+and the Vm only knows how to keep the program counter and follow the `Flow` an opcode returns.
+`OpcodeSet::standard()` is the puzzle's acc/jmp/nop; later puzzles register their own opcodes on
+top instead of writing a new interpreter.
+
+Names are only looked up once, when a line is decoded: the Instruction holds on to its opcode, so
+running it is a call through the trait object. The analyses that don't run anything (repair, the
+disassembler) ask the opcode what it does to the program counter (`FlowKind`) and what it flips
+into, instead of going by its name.
+
+Operands are either a number or a register. There are 26 registers, `a` to `z`, and the
+accumulator is register `a`. Arithmetic is checked, so an overflow is a VmError instead of a panic
+(or a quietly wrapped answer in release builds).
+*/
+use super::vm::{Instruction, ParseInstructionError};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+use std::str::FromStr;
+
+pub const REGISTERS: usize = 26;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Register(usize),
+    Value(i64),
+}
+
+impl FromStr for Operand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_lowercase() => {
+                Ok(Operand::Register(c as usize - 'a' as usize))
+            }
+            _ => s
+                .parse()
+                .map(Operand::Value)
+                .map_err(|_| format!("expected a register or a number, found: {}", s)),
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Register(r) => write!(f, "{}", (b'a' + *r as u8) as char),
+            Operand::Value(n) => write!(f, "{:+}", n),
+        }
+    }
+}
+
+/// Why an instruction couldn't run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmError {
+    Overflow,
+    UnknownRegister(char),
+}
+
+impl Display for VmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VmError::Overflow => write!(f, "arithmetic overflow"),
+            VmError::UnknownRegister(name) => write!(f, "no register {}", name),
+        }
+    }
+}
+
+impl Error for VmError {}
+
+/// Everything an opcode can read or change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cpu {
+    registers: [i64; REGISTERS],
+    output: Vec<i64>,
+}
+
+impl Default for Cpu {
+    fn default() -> Cpu {
+        Cpu {
+            registers: [0; REGISTERS],
+            output: Vec::new(),
+        }
+    }
+}
+
+impl Cpu {
+    /// The value of a number, or what's in a register.
+    pub fn get(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Register(r) => self.registers[r],
+            Operand::Value(n) => n,
+        }
+    }
+
+    /// Writes to a register. Writing to a number does nothing, `Opcode::validate` is the place
+    /// to reject that.
+    pub fn set(&mut self, operand: Operand, value: i64) {
+        if let Operand::Register(r) = operand {
+            self.registers[r] = value;
+        }
+    }
+
+    /// A register by name, `a` to `z`.
+    pub fn register(&self, name: char) -> Result<i64, VmError> {
+        if name.is_ascii_lowercase() {
+            Ok(self.registers[name as usize - 'a' as usize])
+        } else {
+            Err(VmError::UnknownRegister(name))
+        }
+    }
+
+    pub fn out(&mut self, value: i64) {
+        self.output.push(value);
+    }
+
+    pub fn output(&self) -> &[i64] {
+        &self.output
+    }
+}
+
+/// What happens to the program counter after an opcode runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Next,
+    /// Relative to the instruction that jumped, like `jmp`
+    Jump(i64),
+    Halt,
+}
+
+/// What an opcode does to the program counter, as far as can be told without running it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowKind {
+    /// Always on to the next instruction
+    Next,
+    /// Always jumps, by its first operand
+    Jump,
+    /// Jumps or not depending on the registers
+    Conditional,
+    Halt,
+}
+
+pub trait Opcode {
+    fn name(&self) -> &'static str;
+
+    fn arity(&self) -> usize;
+
+    /// Checks the operands when the program is loaded, e.g. that a destination is a register.
+    fn validate(&self, _operands: &[Operand]) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn execute(&self, operands: &[Operand], cpu: &mut Cpu) -> Result<Flow, VmError>;
+
+    fn flow_kind(&self) -> FlowKind {
+        FlowKind::Next
+    }
+
+    /// The opcode a repair may swap this one for, keeping the operands.
+    fn flipped(&self) -> Option<Rc<dyn Opcode>> {
+        None
+    }
+}
+
+fn register_operand(name: &str, operand: Operand) -> Result<(), String> {
+    match operand {
+        Operand::Register(_) => Ok(()),
+        Operand::Value(_) => Err(format!("{} needs a register, found: {}", name, operand)),
+    }
+}
+
+/// `acc x`, adds x to register a
+pub struct Acc;
+
+impl Opcode for Acc {
+    fn name(&self) -> &'static str {
+        "acc"
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn execute(&self, operands: &[Operand], cpu: &mut Cpu) -> Result<Flow, VmError> {
+        let a = Operand::Register(0);
+        let sum = cpu.get(a).checked_add(cpu.get(operands[0]));
+        cpu.set(a, sum.ok_or(VmError::Overflow)?);
+        Ok(Flow::Next)
+    }
+}
+
+/// `jmp x`
+pub struct Jmp;
+
+impl Opcode for Jmp {
+    fn name(&self) -> &'static str {
+        "jmp"
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn execute(&self, operands: &[Operand], cpu: &mut Cpu) -> Result<Flow, VmError> {
+        Ok(Flow::Jump(cpu.get(operands[0])))
+    }
+
+    fn flow_kind(&self) -> FlowKind {
+        FlowKind::Jump
+    }
+
+    fn flipped(&self) -> Option<Rc<dyn Opcode>> {
+        Some(Rc::new(Nop))
+    }
+}
+
+/// `nop x`, the operand is only there to be flipped into a jmp
+pub struct Nop;
+
+impl Opcode for Nop {
+    fn name(&self) -> &'static str {
+        "nop"
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn execute(&self, _operands: &[Operand], _cpu: &mut Cpu) -> Result<Flow, VmError> {
+        Ok(Flow::Next)
+    }
+
+    fn flipped(&self) -> Option<Rc<dyn Opcode>> {
+        Some(Rc::new(Jmp))
+    }
+}
+
+/// `jnz x y`, jumps by y unless x is zero
+pub struct Jnz;
+
+impl Opcode for Jnz {
+    fn name(&self) -> &'static str {
+        "jnz"
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn execute(&self, operands: &[Operand], cpu: &mut Cpu) -> Result<Flow, VmError> {
+        if cpu.get(operands[0]) != 0 {
+            Ok(Flow::Jump(cpu.get(operands[1])))
+        } else {
+            Ok(Flow::Next)
+        }
+    }
+
+    fn flow_kind(&self) -> FlowKind {
+        FlowKind::Conditional
+    }
+}
+
+/// `cpy x r`, copies x into register r
+pub struct Cpy;
+
+impl Opcode for Cpy {
+    fn name(&self) -> &'static str {
+        "cpy"
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn validate(&self, operands: &[Operand]) -> Result<(), String> {
+        register_operand(self.name(), operands[1])
+    }
+
+    fn execute(&self, operands: &[Operand], cpu: &mut Cpu) -> Result<Flow, VmError> {
+        cpu.set(operands[1], cpu.get(operands[0]));
+        Ok(Flow::Next)
+    }
+}
+
+/// `add x r`, adds x to register r
+pub struct Add;
+
+impl Opcode for Add {
+    fn name(&self) -> &'static str {
+        "add"
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn validate(&self, operands: &[Operand]) -> Result<(), String> {
+        register_operand(self.name(), operands[1])
+    }
+
+    fn execute(&self, operands: &[Operand], cpu: &mut Cpu) -> Result<Flow, VmError> {
+        let sum = cpu.get(operands[1]).checked_add(cpu.get(operands[0]));
+        cpu.set(operands[1], sum.ok_or(VmError::Overflow)?);
+        Ok(Flow::Next)
+    }
+}
+
+/// `out x`, appends x to the output
+pub struct Out;
+
+impl Opcode for Out {
+    fn name(&self) -> &'static str {
+        "out"
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn execute(&self, operands: &[Operand], cpu: &mut Cpu) -> Result<Flow, VmError> {
+        cpu.out(cpu.get(operands[0]));
+        Ok(Flow::Next)
+    }
+}
+
+/// `hlt`, stops the program where it is
+pub struct Hlt;
+
+impl Opcode for Hlt {
+    fn name(&self) -> &'static str {
+        "hlt"
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn execute(&self, _operands: &[Operand], _cpu: &mut Cpu) -> Result<Flow, VmError> {
+        Ok(Flow::Halt)
+    }
+
+    fn flow_kind(&self) -> FlowKind {
+        FlowKind::Halt
+    }
+}
+
+#[derive(Default)]
+pub struct OpcodeSet {
+    opcodes: Vec<Rc<dyn Opcode>>,
+}
+
+impl Debug for OpcodeSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.opcodes.iter().map(|o| o.name()))
+            .finish()
+    }
+}
+
+impl OpcodeSet {
+    /// acc, jmp and nop, the puzzle's own opcodes.
+    pub fn standard() -> OpcodeSet {
+        let mut set = OpcodeSet::default();
+        set.register(Acc).register(Jmp).register(Nop);
+        set
+    }
+
+    /// The standard opcodes plus jnz, cpy, add, out and hlt.
+    pub fn extended() -> OpcodeSet {
+        let mut set = OpcodeSet::standard();
+        set.register(Jnz)
+            .register(Cpy)
+            .register(Add)
+            .register(Out)
+            .register(Hlt);
+        set
+    }
+
+    /// Adds an opcode, replacing any opcode with the same name.
+    pub fn register<O: Opcode + 'static>(&mut self, opcode: O) -> &mut OpcodeSet {
+        self.opcodes.retain(|o| o.name() != opcode.name());
+        self.opcodes.push(Rc::new(opcode));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Rc<dyn Opcode>> {
+        self.opcodes.iter().find(|o| o.name() == name)
+    }
+
+    /// One `name operand operand...` line.
+    pub fn decode(&self, line: &str) -> Result<Instruction, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or("expected an opcode")?;
+        let opcode = self
+            .get(name)
+            .ok_or_else(|| format!("unknown operation: {}", name))?;
+        let operands = words
+            .map(str::parse)
+            .collect::<Result<Vec<Operand>, String>>()?;
+        if operands.len() != opcode.arity() {
+            return Err(format!(
+                "{} takes {} operand(s), found {}",
+                name,
+                opcode.arity(),
+                operands.len()
+            ));
+        }
+        opcode.validate(&operands)?;
+        Ok(Instruction::new(Rc::clone(opcode), operands))
+    }
+
+    /// One instruction per line, blank lines are skipped.
+    pub fn parse_program(&self, source: &str) -> Result<Vec<Instruction>, ParseInstructionError> {
+        source
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| {
+                self.decode(line)
+                    .map_err(|message| ParseInstructionError::new(idx + 1, message))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day08::vm::{State, Vm};
+
+    const EXAMPLE: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+    fn load(opcodes: OpcodeSet, source: &str) -> Vm {
+        let program = opcodes.parse_program(source).unwrap();
+        Vm::with_opcodes(opcodes, program)
+    }
+
+    #[test]
+    fn the_standard_set_should_run_the_puzzle() {
+        let mut vm = load(OpcodeSet::standard(), EXAMPLE);
+        assert_eq!(vm.run(), State::LoopDetected { pc: 1 });
+        assert_eq!(vm.cpu().register('a'), Ok(5));
+
+        let fixed = EXAMPLE.replace("jmp -4", "nop -4");
+        let mut vm = load(OpcodeSet::standard(), &fixed);
+        assert_eq!(vm.run(), State::Halted);
+        assert_eq!(vm.acc(), 8);
+    }
+
+    #[test]
+    fn it_should_run_extended_opcodes() {
+        let source = "cpy 3 b\nout b\nadd -1 b\njnz b -2\nacc +7\nhlt\nacc +1";
+        let mut vm = load(OpcodeSet::extended(), source);
+        vm.set_loop_detection(false);
+        assert_eq!(vm.run(), State::Halted);
+        assert_eq!(vm.cpu().output(), &[3, 2, 1]);
+        assert_eq!(vm.cpu().register('a'), Ok(7));
+        assert_eq!(vm.cpu().register('b'), Ok(0));
+        assert_eq!(vm.cpu().register('A'), Err(VmError::UnknownRegister('A')));
+
+        // the loop detection would have stopped the second time round
+        let mut vm = load(OpcodeSet::extended(), source);
+        assert_eq!(vm.run(), State::LoopDetected { pc: 1 });
+    }
+
+    #[test]
+    fn it_should_fault_on_overflow() {
+        let mut vm = load(OpcodeSet::extended(), "cpy 9223372036854775807 b\nadd 1 b");
+        assert_eq!(
+            vm.run(),
+            State::Faulted {
+                pc: 1,
+                error: VmError::Overflow
+            }
+        );
+        assert_eq!(vm.cpu().register('b'), Ok(i64::MAX));
+    }
+
+    struct Dbl;
+
+    impl Opcode for Dbl {
+        fn name(&self) -> &'static str {
+            "dbl"
+        }
+
+        fn arity(&self) -> usize {
+            1
+        }
+
+        fn validate(&self, operands: &[Operand]) -> Result<(), String> {
+            register_operand(self.name(), operands[0])
+        }
+
+        fn execute(&self, operands: &[Operand], cpu: &mut Cpu) -> Result<Flow, VmError> {
+            let doubled = cpu.get(operands[0]).checked_mul(2);
+            cpu.set(operands[0], doubled.ok_or(VmError::Overflow)?);
+            if cpu.get(operands[0]) > 100 {
+                Ok(Flow::Halt)
+            } else {
+                Ok(Flow::Next)
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_run_registered_opcodes() {
+        let mut opcodes = OpcodeSet::standard();
+        opcodes.register(Dbl);
+        let mut vm = load(opcodes, "acc +3\ndbl a\njmp -1");
+        vm.set_loop_detection(false);
+        for _ in 0..3 {
+            assert_eq!(vm.step(), State::Running);
+        }
+        assert_eq!(vm.acc(), 6);
+        assert_eq!(vm.run(), State::Halted);
+        assert_eq!((vm.pc(), vm.acc()), (1, 192));
+    }
+
+    #[test]
+    fn it_should_reject_programs_the_opcodes_dont_accept() {
+        let error = |source| {
+            OpcodeSet::extended()
+                .parse_program(source)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(error("nop +0\nmul +2"), "line 2: unknown operation: mul");
+        assert_eq!(error("jnz a"), "line 1: jnz takes 2 operand(s), found 1");
+        assert_eq!(error("cpy 1 2"), "line 1: cpy needs a register, found: +2");
+        assert_eq!(
+            error("out ab"),
+            "line 1: expected a register or a number, found: ab"
+        );
+        assert!(OpcodeSet::standard().parse_program("out a").is_err());
+    }
+}
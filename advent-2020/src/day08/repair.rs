@@ -0,0 +1,188 @@
+/*
+Part 2 without rerunning the program for every candidate.
+Every instruction has exactly one successor (pc + 1, or pc + n for a jmp), so the program is a
+graph where "the end" is node `len`. Walking the edges backwards from the end finds every
+instruction that already terminates. Then one forward walk over the broken program looks for a
+jmp/nop that, once flipped, lands on one of those. Both passes touch each instruction once.
+If the program already halted, none of the instructions on that forward walk reach the end, so
+the way out after the flip can't come back through the flipped instruction, and running the
+patched program once gives the accumulator.
+*/
+use super::opcode::VmError;
+use super::vm::{Instruction, State, Vm};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub index: usize,
+    pub original: Instruction,
+    pub patched: Instruction,
+    /// The accumulator once the patched program halts
+    pub acc: i64,
+}
+
+impl Display for Repair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "patched {}: {} -> {}, acc {}",
+            self.index, self.original, self.patched, self.acc
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairError {
+    /// The program halts as it is, there's nothing to flip
+    AlreadyHalts,
+    NoFlip,
+    /// The patched program halts, but the instruction at `pc` couldn't run on the way
+    Faulted {
+        pc: usize,
+        error: VmError,
+    },
+}
+
+impl Display for RepairError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepairError::AlreadyHalts => write!(f, "the program already halts without a flip"),
+            RepairError::NoFlip => write!(f, "no single jmp/nop flip makes this program halt"),
+            RepairError::Faulted { pc, error } => {
+                write!(f, "the patched program faulted at pc {}: {}", pc, error)
+            }
+        }
+    }
+}
+
+impl Error for RepairError {}
+
+/// Where the instruction at `pc` goes next, if that's inside the program or exactly its end.
+pub(super) fn successor(pc: usize, instruction: &Instruction, len: usize) -> Option<usize> {
+    let target = instruction.next_pc(pc);
+    if target < 0 || target > len as i64 {
+        None
+    } else {
+        Some(target as usize)
+    }
+}
+
+/// `ends[pc]` is true if running from `pc` halts. `ends[len]` is the end itself.
+fn reaches_end(program: &[Instruction]) -> Vec<bool> {
+    let len = program.len();
+    let mut predecessors = vec![Vec::new(); len + 1];
+    for (pc, instruction) in program.iter().enumerate() {
+        if let Some(next) = successor(pc, instruction, len) {
+            predecessors[next].push(pc);
+        }
+    }
+
+    let mut ends = vec![false; len + 1];
+    ends[len] = true;
+    let mut stack = vec![len];
+    while let Some(node) = stack.pop() {
+        for &pc in &predecessors[node] {
+            if !ends[pc] {
+                ends[pc] = true;
+                stack.push(pc);
+            }
+        }
+    }
+    ends
+}
+
+/// Finds the single jmp/nop flip that makes the program halt, on the path the program
+/// actually takes.
+pub fn repair(program: &[Instruction]) -> Result<Repair, RepairError> {
+    let len = program.len();
+    let ends = reaches_end(program);
+    if ends[0] {
+        return Err(RepairError::AlreadyHalts);
+    }
+    let mut visited = vec![false; len];
+    let mut pc = 0;
+
+    while pc < len && !visited[pc] {
+        visited[pc] = true;
+        let original = &program[pc];
+        if let Some(patched) = original.flipped() {
+            if successor(pc, &patched, len).is_some_and(|next| ends[next]) {
+                let mut fixed = program.to_vec();
+                fixed[pc] = patched.clone();
+                let mut vm = Vm::new(fixed);
+                return match vm.run() {
+                    State::Halted => Ok(Repair {
+                        index: pc,
+                        original: original.clone(),
+                        patched,
+                        acc: vm.acc(),
+                    }),
+                    State::Faulted { pc, error } => Err(RepairError::Faulted { pc, error }),
+                    // can't happen, the way out never comes back through the flip
+                    _ => Err(RepairError::NoFlip),
+                };
+            }
+        }
+        pc = successor(pc, original, len).ok_or(RepairError::NoFlip)?;
+    }
+    Err(RepairError::NoFlip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day08::vm::parse_program;
+
+    #[test]
+    fn it_should_find_which_instructions_reach_the_end() {
+        let program = parse_program("nop +0\njmp +0\nacc +1\njmp -1\nacc +2").unwrap();
+        // 2 falls into the jmp -1 loop with 3, 4 runs off the end
+        assert_eq!(
+            reaches_end(&program),
+            vec![false, false, false, false, true, true]
+        );
+    }
+
+    #[test]
+    fn it_should_repair_the_example() {
+        let program = parse_program(
+            "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6",
+        )
+        .unwrap();
+        let repair = repair(&program).unwrap();
+        assert_eq!(repair.index, 7);
+        assert_eq!(repair.patched, "nop -4".parse().unwrap());
+        assert_eq!(repair.acc, 8);
+        assert_eq!(repair.to_string(), "patched 7: jmp -4 -> nop -4, acc 8");
+    }
+
+    #[test]
+    fn it_should_give_up_when_no_flip_helps() {
+        let program = parse_program("jmp +0\nacc +1\njmp -2").unwrap();
+        assert_eq!(repair(&program), Err(RepairError::NoFlip));
+    }
+
+    #[test]
+    fn it_should_refuse_a_program_that_already_halts() {
+        let program = parse_program("nop +0\nacc +1\njmp +1").unwrap();
+        assert_eq!(repair(&program), Err(RepairError::AlreadyHalts));
+        assert_eq!(repair(&[]), Err(RepairError::AlreadyHalts));
+    }
+
+    #[test]
+    fn it_should_add_up_the_accumulator_on_the_way_out() {
+        // flipping the jmp +0 skips to the acc +3 instructions, the acc +100 only runs before it
+        let program = parse_program("acc +100\njmp +0\nacc +3\nacc +3").unwrap();
+        let repair = repair(&program).unwrap();
+        assert_eq!((repair.index, repair.acc), (1, 106));
+        let program = parse_program("acc +9223372036854775807\njmp +0\nacc +1").unwrap();
+        assert_eq!(
+            super::repair(&program),
+            Err(RepairError::Faulted {
+                pc: 2,
+                error: VmError::Overflow
+            })
+        );
+    }
+}
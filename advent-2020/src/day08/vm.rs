@@ -0,0 +1,427 @@
+/*
+The handheld console, take two. Instead of matching on strings every step, the program is decoded
+once into Instructions, each holding the opcode its name resolved to, and the Vm keeps the program
+counter, the registers and which instructions already ran. What an instruction does is up to its
+opcode (see opcode.rs), the Vm only follows the Flow it returns. Jumps are checked before they happen, so a `jmp -1000` on line 3
+is an OutOfBounds state instead of a usize underflow panic.
+*/
+use super::opcode::{Cpu, Flow, FlowKind, Opcode, OpcodeSet, Operand, VmError};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// An opcode with its operands, decoded by an OpcodeSet.
+#[derive(Clone)]
+pub struct Instruction {
+    opcode: Rc<dyn Opcode>,
+    operands: Vec<Operand>,
+}
+
+impl Debug for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Instruction({})", self)
+    }
+}
+
+/// Two instructions are the same if their opcodes have the same name and the operands match.
+impl PartialEq for Instruction {
+    fn eq(&self, other: &Instruction) -> bool {
+        self.opcode() == other.opcode() && self.operands == other.operands
+    }
+}
+
+impl Eq for Instruction {}
+
+impl FromStr for Instruction {
+    type Err = ParseInstructionError;
+
+    /// Decodes with the standard opcodes.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OpcodeSet::standard()
+            .decode(s)
+            .map_err(|message| ParseInstructionError { line: 1, message })
+    }
+}
+
+impl Instruction {
+    pub(super) fn new(opcode: Rc<dyn Opcode>, operands: Vec<Operand>) -> Instruction {
+        Instruction { opcode, operands }
+    }
+
+    /// The opcode's name.
+    pub fn opcode(&self) -> &'static str {
+        self.opcode.name()
+    }
+
+    pub fn operands(&self) -> &[Operand] {
+        &self.operands
+    }
+
+    pub fn flow_kind(&self) -> FlowKind {
+        self.opcode.flow_kind()
+    }
+
+    /// The same operands under the opcode this one flips into, e.g. a jmp into a nop.
+    pub fn flipped(&self) -> Option<Instruction> {
+        let opcode = self.opcode.flipped()?;
+        Some(Instruction::new(opcode, self.operands.clone()))
+    }
+
+    /// Where the program counter goes after this instruction runs at `pc`, as far as that can
+    /// be told without running it: a jump by a number goes there, anything else (including a
+    /// conditional jump) to the next instruction. It may well be outside the program.
+    pub fn next_pc(&self, pc: usize) -> i64 {
+        match (self.flow_kind(), self.operands.as_slice()) {
+            (FlowKind::Jump, [Operand::Value(n), ..]) => (pc as i64).saturating_add(*n),
+            _ => pc as i64 + 1,
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.opcode())?;
+        for operand in &self.operands {
+            write!(f, " {}", operand)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseInstructionError {
+    line: usize,
+    message: String,
+}
+
+impl ParseInstructionError {
+    pub(super) fn new(line: usize, message: String) -> ParseInstructionError {
+        ParseInstructionError { line, message }
+    }
+}
+
+impl Display for ParseInstructionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseInstructionError {}
+
+/// One standard instruction per line, blank lines are skipped.
+pub fn parse_program(input: &str) -> Result<Vec<Instruction>, ParseInstructionError> {
+    OpcodeSet::standard().parse_program(input)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Running,
+    /// The program counter landed exactly one past the last instruction, or an opcode said to stop
+    Halted,
+    /// The instruction at `pc` was about to run a second time
+    LoopDetected {
+        pc: usize,
+    },
+    /// The instruction at `pc` tried to jump to `target`, which is outside the program
+    OutOfBounds {
+        pc: usize,
+        target: i64,
+    },
+    /// The instruction at `pc` couldn't run, so the program counter stays on it
+    Faulted {
+        pc: usize,
+        error: VmError,
+    },
+}
+
+impl Display for State {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            State::Running => write!(f, "running"),
+            State::Halted => write!(f, "halted"),
+            State::LoopDetected { pc } => write!(f, "loop detected at pc {}", pc),
+            State::OutOfBounds { pc, target } => {
+                write!(f, "jump out of bounds at pc {} (target {})", pc, target)
+            }
+            State::Faulted { pc, error } => write!(f, "faulted at pc {}: {}", pc, error),
+        }
+    }
+}
+
+/// Where the program counter ends up when the instruction at `pc` sends it to `target` in a
+/// program `len` instructions long, and whether the program is still running after that.
+fn land(pc: usize, target: i64, len: usize) -> (usize, State) {
+    if target < 0 || target > len as i64 {
+        (pc, State::OutOfBounds { pc, target })
+    } else if target as usize == len {
+        (len, State::Halted)
+    } else {
+        (target as usize, State::Running)
+    }
+}
+
+#[derive(Debug)]
+pub struct Vm {
+    opcodes: OpcodeSet,
+    program: Vec<Instruction>,
+    pc: usize,
+    cpu: Cpu,
+    visited: Vec<bool>,
+    detect_loops: bool,
+    state: State,
+}
+
+impl Vm {
+    /// Runs `program` with the standard opcodes.
+    pub fn new(program: Vec<Instruction>) -> Vm {
+        Vm::with_opcodes(OpcodeSet::standard(), program)
+    }
+
+    /// Runs `program` with `opcodes` at hand, for whoever wants to decode more instructions
+    /// (the debugger's patches). The instructions already know their own opcodes.
+    pub fn with_opcodes(opcodes: OpcodeSet, program: Vec<Instruction>) -> Vm {
+        let visited = vec![false; program.len()];
+        let state = if program.is_empty() {
+            State::Halted
+        } else {
+            State::Running
+        };
+        Vm {
+            opcodes,
+            program,
+            pc: 0,
+            cpu: Cpu::default(),
+            visited,
+            detect_loops: true,
+            state,
+        }
+    }
+
+    /// With conditional jumps running an instruction twice isn't a loop, so this turns the
+    /// check off. It's on to begin with.
+    pub fn set_loop_detection(&mut self, on: bool) {
+        self.detect_loops = on;
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// The accumulator, register `a`.
+    pub fn acc(&self) -> i64 {
+        self.cpu.get(Operand::Register(0))
+    }
+
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn opcodes(&self) -> &OpcodeSet {
+        &self.opcodes
+    }
+
+    pub fn program(&self) -> &[Instruction] {
+        &self.program
+    }
+
+    /// The instruction the next step would run, if the Vm is still running.
+    pub fn current(&self) -> Option<&Instruction> {
+        match self.state {
+            State::Running => self.program.get(self.pc),
+            _ => None,
+        }
+    }
+
+    /// Swaps the instruction at `index` for another one and returns the old one. Which
+    /// instructions already ran is kept, so patching doesn't hide a loop.
+    pub fn patch(&mut self, index: usize, instruction: Instruction) -> Option<Instruction> {
+        let slot = self.program.get_mut(index)?;
+        Some(std::mem::replace(slot, instruction))
+    }
+
+    /// Runs the instruction at the program counter and works out where it sends the program
+    /// counter, None if it halts the program.
+    fn execute(&mut self) -> Result<Option<i64>, VmError> {
+        let instruction = &self.program[self.pc];
+        match instruction
+            .opcode
+            .execute(&instruction.operands, &mut self.cpu)?
+        {
+            Flow::Next => Ok(Some(self.pc as i64 + 1)),
+            Flow::Jump(offset) => (self.pc as i64)
+                .checked_add(offset)
+                .map(Some)
+                .ok_or(VmError::Overflow),
+            Flow::Halt => Ok(None),
+        }
+    }
+
+    /// Runs the instruction at the program counter, unless the Vm already stopped.
+    pub fn step(&mut self) -> State {
+        if self.state != State::Running {
+            return self.state;
+        }
+        if self.detect_loops && self.visited[self.pc] {
+            self.state = State::LoopDetected { pc: self.pc };
+            return self.state;
+        }
+
+        match self.execute() {
+            Ok(Some(target)) => {
+                self.visited[self.pc] = true;
+                let (pc, state) = land(self.pc, target, self.program.len());
+                self.pc = pc;
+                self.state = state;
+            }
+            Ok(None) => {
+                self.visited[self.pc] = true;
+                self.state = State::Halted;
+            }
+            Err(error) => self.state = State::Faulted { pc: self.pc, error },
+        }
+        self.state
+    }
+
+    /// Steps until the program halts, loops, jumps somewhere it shouldn't or faults.
+    pub fn run(&mut self) -> State {
+        while self.step() == State::Running {}
+        self.state
+    }
+
+    /// Back to the start, keeping the program, the opcodes and whether loops are checked.
+    pub fn reset(&mut self) {
+        let detect_loops = self.detect_loops;
+        *self = Vm::with_opcodes(
+            std::mem::take(&mut self.opcodes),
+            std::mem::take(&mut self.program),
+        );
+        self.detect_loops = detect_loops;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+    #[test]
+    fn it_should_decode_instructions() {
+        let acc: Instruction = "acc -99".parse().unwrap();
+        assert_eq!(acc.opcode(), "acc");
+        assert_eq!(acc.operands(), &[Operand::Value(-99)]);
+        assert_eq!(acc.to_string(), "acc -99");
+        let nop: Instruction = "  nop +0 ".parse().unwrap();
+        assert_eq!(nop.to_string(), "nop +0");
+        assert_eq!(nop.flipped().unwrap().to_string(), "jmp +0");
+        assert_eq!(nop.flipped().unwrap().flow_kind(), FlowKind::Jump);
+        assert!(acc.flipped().is_none());
+    }
+
+    #[test]
+    fn it_should_say_which_line_is_bad() {
+        let error = parse_program("nop +0\nmul +2\nacc +1").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unknown operation: mul");
+        let error = parse_program("nop +0\n\nacc one").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 3: expected a register or a number, found: one"
+        );
+        let error = parse_program("jmp").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: jmp takes 1 operand(s), found 0");
+    }
+
+    #[test]
+    fn it_should_know_where_a_jump_goes_without_running_it() {
+        let program = parse_program("jmp -3\nacc +5\njmp a").unwrap();
+        assert_eq!(program[0].next_pc(4), 1);
+        assert_eq!(program[1].next_pc(4), 5);
+        // a jump by a register depends on what's in it
+        assert_eq!(program[2].next_pc(4), 5);
+    }
+
+    #[test]
+    fn it_should_stop_before_running_an_instruction_twice() {
+        let mut vm = Vm::new(parse_program(EXAMPLE).unwrap());
+        assert_eq!(vm.run(), State::LoopDetected { pc: 1 });
+        assert_eq!(vm.acc(), 5);
+        // once stopped, stepping does nothing
+        assert_eq!(vm.step(), State::LoopDetected { pc: 1 });
+        assert_eq!(vm.acc(), 5);
+    }
+
+    #[test]
+    fn it_should_step_one_instruction_at_a_time() {
+        let mut vm = Vm::new(parse_program(EXAMPLE).unwrap());
+        assert_eq!(vm.step(), State::Running);
+        assert_eq!((vm.pc(), vm.acc()), (1, 0));
+        vm.step();
+        assert_eq!((vm.pc(), vm.acc()), (2, 1));
+        vm.step();
+        assert_eq!((vm.pc(), vm.acc()), (6, 1));
+        vm.reset();
+        assert_eq!((vm.pc(), vm.acc(), vm.state()), (0, 0, State::Running));
+        assert!(vm.opcodes().get("acc").is_some());
+    }
+
+    #[test]
+    fn it_should_halt_after_the_last_instruction() {
+        let mut vm = Vm::new(parse_program("nop +0\nacc +2\njmp +1").unwrap());
+        assert_eq!(vm.run(), State::Halted);
+        assert_eq!(vm.acc(), 2);
+        assert_eq!(Vm::new(Vec::new()).state(), State::Halted);
+    }
+
+    #[test]
+    fn it_should_fault_instead_of_overflowing() {
+        let mut vm = Vm::new(parse_program("acc +9223372036854775807\nnop +0\nacc +1").unwrap());
+        let overflow = State::Faulted {
+            pc: 2,
+            error: VmError::Overflow,
+        };
+        assert_eq!(vm.run(), overflow);
+        assert_eq!((vm.pc(), vm.acc()), (2, i64::MAX));
+        assert_eq!(
+            vm.state().to_string(),
+            "faulted at pc 2: arithmetic overflow"
+        );
+        let mut vm = Vm::new(parse_program("nop +0\njmp +9223372036854775807").unwrap());
+        assert_eq!(
+            vm.run(),
+            State::Faulted {
+                pc: 1,
+                error: VmError::Overflow
+            }
+        );
+    }
+
+    #[test]
+    fn instructions_should_bring_their_own_opcodes() {
+        // decoded with the extended set, so it runs even in a Vm with the standard one
+        let program = OpcodeSet::extended().parse_program("out +1").unwrap();
+        let mut vm = Vm::new(program);
+        assert_eq!(vm.run(), State::Halted);
+        assert_eq!(vm.cpu().output(), &[1]);
+    }
+
+    #[test]
+    fn it_should_not_underflow_on_a_jump_before_the_start() {
+        let mut vm = Vm::new(parse_program("acc +1\njmp -5").unwrap());
+        assert_eq!(vm.run(), State::OutOfBounds { pc: 1, target: -4 });
+        let mut vm = Vm::new(parse_program("jmp +2").unwrap());
+        assert_eq!(vm.run(), State::OutOfBounds { pc: 0, target: 2 });
+    }
+}
@@ -0,0 +1,234 @@
+/*
+XMAS: after a preamble of N numbers, every number should be the sum of two different numbers among
+the N before it.
+
+The window keeps a count of every value in it, so checking a number is one lookup per value in the
+window, and moving on is one insert and one removal, instead of rescanning all the pairs.
+Part 2 wants a contiguous run (at least two long) adding up to the invalid number. Everything is
+positive, so a window that grows on the right while the sum is too small and shrinks on the left
+while it's too big will find it in one pass.
+*/
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+pub const PREAMBLE: usize = 25;
+
+pub fn day09_1(input: &str, preamble: usize) -> Result<u64, XmasError> {
+    let numbers = parse_numbers(input)?;
+    first_invalid(&numbers, preamble)
+        .map(|(_, n)| n)
+        .ok_or(XmasError::NoInvalidNumber)
+}
+
+pub fn day09_2(input: &str, preamble: usize) -> Result<u64, XmasError> {
+    let numbers = parse_numbers(input)?;
+    let (_, invalid) = first_invalid(&numbers, preamble).ok_or(XmasError::NoInvalidNumber)?;
+    let range = contiguous_sum(&numbers, invalid).ok_or(XmasError::NoWeakness(invalid))?;
+    Ok(weakness(&numbers[range]))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmasError {
+    Parse { line: usize, text: String },
+    NoInvalidNumber,
+    NoWeakness(u64),
+}
+
+impl Display for XmasError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XmasError::Parse { line, text } => write!(f, "line {}: not a number: {}", line, text),
+            XmasError::NoInvalidNumber => write!(f, "every number is a sum of two before it"),
+            XmasError::NoWeakness(n) => write!(f, "no contiguous range adds up to {}", n),
+        }
+    }
+}
+
+impl Error for XmasError {}
+
+fn parse_numbers(input: &str) -> Result<Vec<u64>, XmasError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(idx, line)| {
+            line.parse().map_err(|_| XmasError::Parse {
+                line: idx + 1,
+                text: line.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The last `size` numbers seen.
+struct Window {
+    size: usize,
+    numbers: VecDeque<u64>,
+    counts: HashMap<u64, usize>,
+}
+
+impl Window {
+    fn new(size: usize) -> Window {
+        Window {
+            size,
+            numbers: VecDeque::with_capacity(size + 1),
+            counts: HashMap::with_capacity(size + 1),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.numbers.len() == self.size
+    }
+
+    /// Two different values in the window add up to `n`.
+    fn is_sum(&self, n: u64) -> bool {
+        self.counts
+            .keys()
+            .any(|&a| a < n && n - a != a && self.counts.contains_key(&(n - a)))
+    }
+
+    fn push(&mut self, n: u64) {
+        self.numbers.push_back(n);
+        *self.counts.entry(n).or_insert(0) += 1;
+        if self.numbers.len() > self.size {
+            let old = self.numbers.pop_front().unwrap();
+            let count = self.counts.get_mut(&old).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&old);
+            }
+        }
+    }
+}
+
+/// The index and value of the first number after the preamble that isn't a sum of two
+/// different numbers among the `preamble` before it.
+pub fn first_invalid(numbers: &[u64], preamble: usize) -> Option<(usize, u64)> {
+    let mut window = Window::new(preamble);
+    for (idx, &n) in numbers.iter().enumerate() {
+        if window.is_full() && !window.is_sum(n) {
+            return Some((idx, n));
+        }
+        window.push(n);
+    }
+    None
+}
+
+/// The first run of at least two numbers adding up to `target`.
+pub fn contiguous_sum(numbers: &[u64], target: u64) -> Option<Range<usize>> {
+    let (mut start, mut sum) = (0, 0);
+    for end in 0..numbers.len() {
+        sum += numbers[end];
+        while sum > target && start < end {
+            sum -= numbers[start];
+            start += 1;
+        }
+        if sum == target && end > start {
+            return Some(start..end + 1);
+        }
+    }
+    None
+}
+
+/// The smallest and largest number in the run, added together.
+fn weakness(run: &[u64]) -> u64 {
+    run.iter().min().unwrap() + run.iter().max().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "35
+        20
+        15
+        25
+        47
+        40
+        62
+        55
+        65
+        95
+        102
+        117
+        150
+        182
+        127
+        219
+        299
+        277
+        309
+        576";
+
+    fn one_to_twenty_five() -> Vec<u64> {
+        (1..=25).collect()
+    }
+
+    #[test]
+    fn it_should_check_numbers_against_the_preamble() {
+        let mut numbers = one_to_twenty_five();
+        for (next, valid) in &[(26, true), (49, true), (100, false), (50, false)] {
+            numbers.push(*next);
+            let invalid = first_invalid(&numbers, PREAMBLE);
+            assert_eq!(invalid.is_none(), *valid, "{}", next);
+            numbers.pop();
+        }
+    }
+
+    #[test]
+    fn it_should_check_numbers_after_the_window_moves() {
+        // 20 first, then 1 to 25 without 20, then 45
+        let mut numbers = vec![20];
+        numbers.extend((1..=25).filter(|&n| n != 20));
+        numbers.push(45);
+        for (next, valid) in &[(26, true), (65, false), (64, true), (66, true)] {
+            numbers.push(*next);
+            assert_eq!(
+                first_invalid(&numbers, PREAMBLE).is_none(),
+                *valid,
+                "{}",
+                next
+            );
+            numbers.pop();
+        }
+    }
+
+    #[test]
+    fn it_should_find_the_first_invalid_number() {
+        assert_eq!(day09_1(EXAMPLE, 5), Ok(127));
+        let numbers = parse_numbers(EXAMPLE).unwrap();
+        assert_eq!(first_invalid(&numbers, 5), Some((14, 127)));
+    }
+
+    #[test]
+    fn it_should_find_the_encryption_weakness() {
+        let numbers = parse_numbers(EXAMPLE).unwrap();
+        assert_eq!(contiguous_sum(&numbers, 127), Some(2..6));
+        assert_eq!(day09_2(EXAMPLE, 5), Ok(62));
+        // a number on its own doesn't count as a range
+        assert_eq!(contiguous_sum(&[1, 5, 3], 5), None);
+    }
+
+    #[test]
+    fn a_number_should_not_pair_up_with_its_own_value() {
+        assert_eq!(first_invalid(&[5, 5, 10], 2), Some((2, 10)));
+        assert_eq!(first_invalid(&[5, 1, 5, 10], 3), Some((3, 10)));
+        assert_eq!(first_invalid(&[1, 2, 3, 5], 2), None);
+    }
+
+    #[test]
+    fn it_should_report_bad_input() {
+        assert_eq!(
+            day09_1("1\n2\nthree", 2),
+            Err(XmasError::Parse {
+                line: 3,
+                text: "three".to_string()
+            })
+        );
+        assert_eq!(day09_1("1\n2\n3", 2), Err(XmasError::NoInvalidNumber));
+        assert_eq!(day09_2("1\n2\n30", 2), Err(XmasError::NoWeakness(30)));
+    }
+}
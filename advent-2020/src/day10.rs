@@ -0,0 +1,183 @@
+/*
+Adapters. Sorted, with the outlet (0) in front and the device (highest + 3) at the end, the chain
+is just the sorted list, and part 1 counts the gaps between neighbours.
+
+Part 2 counts every subset of adapters that still works. Walking the sorted chain, the number of
+ways to reach an adapter is the sum of the ways to reach each of the (at most three) adapters
+within 3 jolts below it. That only holds with every joltage showing up once (the puzzle says they
+do), so a repeated adapter is an error rather than a wrong count. That's one pass, and the counts get big, hence u128 (and checked sums,
+since about 150 adapters in a row is enough to overflow even that).
+*/
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+pub fn day10_1(input: &str) -> Result<usize, AdapterError> {
+    let distribution = differences(&chain(input)?);
+    Ok(distribution[1] * distribution[3])
+}
+
+pub fn day10_2(input: &str) -> Result<u128, AdapterError> {
+    arrangements(&chain(input)?)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdapterError {
+    Parse {
+        line: usize,
+        text: String,
+    },
+    /// Nothing can get from `from` jolts to `to` jolts
+    Gap {
+        from: u32,
+        to: u32,
+    },
+    /// The device would be rated above u32::MAX
+    TooManyJolts(u32),
+    /// Two adapters (or an adapter and the outlet) with the same joltage
+    Duplicate(u32),
+    TooManyArrangements,
+}
+
+impl Display for AdapterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdapterError::Parse { line, text } => {
+                write!(f, "line {}: not a joltage: {}", line, text)
+            }
+            AdapterError::Gap { from, to } => {
+                write!(f, "no adapter between {} and {} jolts", from, to)
+            }
+            AdapterError::TooManyJolts(highest) => {
+                write!(f, "no device can take {} jolts plus 3", highest)
+            }
+            AdapterError::Duplicate(joltage) => {
+                write!(f, "more than one adapter rated {} jolts", joltage)
+            }
+            AdapterError::TooManyArrangements => {
+                write!(f, "too many arrangements to count")
+            }
+        }
+    }
+}
+
+impl Error for AdapterError {}
+
+/// Every joltage in order, from the outlet to the device.
+fn chain(input: &str) -> Result<Vec<u32>, AdapterError> {
+    let mut joltages: Vec<u32> = vec![0];
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        joltages.push(line.parse().map_err(|_| AdapterError::Parse {
+            line: idx + 1,
+            text: line.to_string(),
+        })?);
+    }
+    joltages.sort_unstable();
+    let highest = *joltages.last().unwrap();
+    let device = highest
+        .checked_add(3)
+        .ok_or(AdapterError::TooManyJolts(highest))?;
+    joltages.push(device);
+
+    if let Some(pair) = joltages.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(AdapterError::Duplicate(pair[0]));
+    }
+    if let Some(gap) = joltages.windows(2).find(|pair| pair[1] - pair[0] > 3) {
+        return Err(AdapterError::Gap {
+            from: gap[0],
+            to: gap[1],
+        });
+    }
+    Ok(joltages)
+}
+
+/// How many times each difference (0 to 3 jolts) appears between neighbours in the chain.
+fn differences(chain: &[u32]) -> [usize; 4] {
+    let mut distribution = [0; 4];
+    for pair in chain.windows(2) {
+        distribution[(pair[1] - pair[0]) as usize] += 1;
+    }
+    distribution
+}
+
+/// Ways to get from the first joltage in the chain to the last.
+fn arrangements(chain: &[u32]) -> Result<u128, AdapterError> {
+    let mut ways: Vec<u128> = vec![0; chain.len()];
+    ways[0] = 1;
+    for i in 1..chain.len() {
+        ways[i] = (i.saturating_sub(3)..i)
+            .filter(|&j| chain[i] - chain[j] <= 3)
+            .try_fold(0u128, |sum, j| sum.checked_add(ways[j]))
+            .ok_or(AdapterError::TooManyArrangements)?;
+    }
+    Ok(ways.last().copied().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = "16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4";
+
+    const LARGE: &str = "28\n33\n18\n42\n31\n14\n46\n20\n48\n47\n24\n23\n49\n45\n19\n38\n39\n11\n1\n32\n25\n35\n8\n17\n7\n9\n4\n2\n34\n10\n3";
+
+    #[test]
+    fn it_should_count_the_joltage_differences() {
+        assert_eq!(differences(&chain(SMALL).unwrap()), [0, 7, 0, 5]);
+        assert_eq!(differences(&chain(LARGE).unwrap()), [0, 22, 0, 10]);
+        assert_eq!(day10_1(SMALL), Ok(35));
+        assert_eq!(day10_1(LARGE), Ok(220));
+    }
+
+    #[test]
+    fn it_should_count_the_arrangements() {
+        assert_eq!(day10_2(SMALL), Ok(8));
+        assert_eq!(day10_2(LARGE), Ok(19208));
+    }
+
+    #[test]
+    fn it_should_not_overflow_on_long_chains() {
+        // with every joltage from 1 to 100 the arrangements are tribonacci numbers
+        let input: Vec<String> = (1..=100).map(|n| n.to_string()).collect();
+        let ways = day10_2(&input.join("\n")).unwrap();
+        assert!(ways > u128::from(u64::MAX));
+
+        let input: Vec<String> = (1..=200).map(|n| n.to_string()).collect();
+        assert_eq!(
+            day10_2(&input.join("\n")),
+            Err(AdapterError::TooManyArrangements)
+        );
+    }
+
+    #[test]
+    fn it_should_not_overflow_the_device_joltage() {
+        assert_eq!(
+            day10_1("4294967294"),
+            Err(AdapterError::TooManyJolts(u32::MAX - 1))
+        );
+        assert_eq!(
+            AdapterError::TooManyJolts(u32::MAX - 1).to_string(),
+            "no device can take 4294967294 jolts plus 3"
+        );
+    }
+
+    #[test]
+    fn it_should_report_broken_chains() {
+        assert_eq!(
+            day10_1("1\n2\n9"),
+            Err(AdapterError::Gap { from: 2, to: 9 })
+        );
+        assert_eq!(day10_2("1\n1\n2\n2\n3"), Err(AdapterError::Duplicate(1)));
+        assert_eq!(day10_1("0\n1"), Err(AdapterError::Duplicate(0)));
+        assert_eq!(
+            day10_2("1\nsix"),
+            Err(AdapterError::Parse {
+                line: 2,
+                text: "six".to_string()
+            })
+        );
+    }
+}
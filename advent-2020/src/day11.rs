@@ -0,0 +1,407 @@
+/*
+Seating system, a cellular automaton.
+An empty seat with no occupied neighbours fills up, an occupied seat with too many occupied
+neighbours empties, and floor never changes. Run it until nothing changes and count the people.
+
+Who counts as a neighbour depends on the rule: the 8 cells around a seat, or the first seat
+visible in each of the 8 directions. Either way the seats don't move, so each seat's neighbours
+are worked out once up front and every generation only looks at those lists.
+*/
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+pub fn day11_1(input: &str) -> Result<usize, LayoutError> {
+    settle(input, Rule::Adjacent)
+}
+
+pub fn day11_2(input: &str) -> Result<usize, LayoutError> {
+    settle(input, Rule::LineOfSight)
+}
+
+/// Every generation for `rule` until the layout settles, each one followed by a blank line.
+pub fn day11_generations(input: &str, rule: Rule) -> Result<String, LayoutError> {
+    let mut simulation = Simulation::new(Layout::parse(input)?, rule);
+    let mut out = format!("generation 0\n{}\n", simulation.layout());
+    simulation.run_with(|generation, layout| {
+        out.push_str(&format!("generation {}\n{}\n", generation, layout));
+    });
+    Ok(out)
+}
+
+fn settle(input: &str, rule: Rule) -> Result<usize, LayoutError> {
+    let mut simulation = Simulation::new(Layout::parse(input)?, rule);
+    simulation.run();
+    Ok(simulation.layout().occupied())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Floor,
+    Empty,
+    Occupied,
+}
+
+/// Lines and columns start at 1, lines counting from the top of the input (blank ones too).
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutError {
+    UnknownCell {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    /// A row that isn't as wide as the first one
+    Ragged {
+        line: usize,
+        width: usize,
+        expected: usize,
+    },
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutError::UnknownCell {
+                line,
+                column,
+                found,
+            } => write!(
+                f,
+                "line {}, column {}: expected '.', 'L' or '#', found '{}'",
+                line, column, found
+            ),
+            LayoutError::Ragged {
+                line,
+                width,
+                expected,
+            } => write!(
+                f,
+                "line {}: row is {} wide, expected {}",
+                line, width, expected
+            ),
+        }
+    }
+}
+
+impl Error for LayoutError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl Layout {
+    /// `.` is floor, `L` an empty seat and `#` an occupied one. Every row has to be as wide
+    /// as the first one.
+    pub fn parse(input: &str) -> Result<Layout, LayoutError> {
+        let rows: Vec<(usize, &str)> = input
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .collect();
+        let width = rows.first().map_or(0, |(_, row)| row.chars().count());
+        let mut cells = Vec::with_capacity(width * rows.len());
+        for &(idx, row) in &rows {
+            let found = row.chars().count();
+            if found != width {
+                return Err(LayoutError::Ragged {
+                    line: idx + 1,
+                    width: found,
+                    expected: width,
+                });
+            }
+            for (column, c) in row.chars().enumerate() {
+                cells.push(match c {
+                    '.' => Cell::Floor,
+                    'L' => Cell::Empty,
+                    '#' => Cell::Occupied,
+                    found => {
+                        return Err(LayoutError::UnknownCell {
+                            line: idx + 1,
+                            column: column + 1,
+                            found,
+                        })
+                    }
+                });
+            }
+        }
+        Ok(Layout {
+            width,
+            height: rows.len(),
+            cells,
+        })
+    }
+
+    pub fn occupied(&self) -> usize {
+        self.cells.iter().filter(|&&c| c == Cell::Occupied).count()
+    }
+
+    fn index(&self, row: usize, column: usize) -> usize {
+        row * self.width + column
+    }
+
+    /// The cell one step from (row, column) in direction (dr, dc), if that's still on the map.
+    fn offset(
+        &self,
+        row: usize,
+        column: usize,
+        (dr, dc): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let row = row as isize + dr;
+        let column = column as isize + dc;
+        if row < 0 || column < 0 || row >= self.height as isize || column >= self.width as isize {
+            None
+        } else {
+            Some((row as usize, column as usize))
+        }
+    }
+}
+
+impl Display for Layout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for row in self.cells.chunks(self.width.max(1)) {
+            let line: String = row
+                .iter()
+                .map(|cell| match cell {
+                    Cell::Floor => '.',
+                    Cell::Empty => 'L',
+                    Cell::Occupied => '#',
+                })
+                .collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// The 8 cells around a seat, and it empties at 4 occupied
+    Adjacent,
+    /// The first seat seen in each direction, and it empties at 5 occupied
+    LineOfSight,
+}
+
+impl Rule {
+    fn tolerance(self) -> usize {
+        match self {
+            Rule::Adjacent => 4,
+            Rule::LineOfSight => 5,
+        }
+    }
+
+    /// For every seat, the indexes of the seats it looks at. Floor gets an empty list.
+    fn neighbours(self, layout: &Layout) -> Vec<Vec<usize>> {
+        let mut neighbours = vec![Vec::new(); layout.cells.len()];
+        for row in 0..layout.height {
+            for column in 0..layout.width {
+                let index = layout.index(row, column);
+                if layout.cells[index] == Cell::Floor {
+                    continue;
+                }
+                for &direction in DIRECTIONS.iter() {
+                    let mut at = layout.offset(row, column, direction);
+                    while let Some((r, c)) = at {
+                        let seen = layout.index(r, c);
+                        if layout.cells[seen] != Cell::Floor {
+                            neighbours[index].push(seen);
+                            break;
+                        }
+                        if self == Rule::Adjacent {
+                            break;
+                        }
+                        at = layout.offset(r, c, direction);
+                    }
+                }
+            }
+        }
+        neighbours
+    }
+}
+
+pub struct Simulation {
+    layout: Layout,
+    neighbours: Vec<Vec<usize>>,
+    tolerance: usize,
+    generation: usize,
+}
+
+impl Simulation {
+    pub fn new(layout: Layout, rule: Rule) -> Simulation {
+        Simulation {
+            neighbours: rule.neighbours(&layout),
+            tolerance: rule.tolerance(),
+            layout,
+            generation: 0,
+        }
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Moves everyone at once, returns false if nobody moved.
+    pub fn step(&mut self) -> bool {
+        let cells = &self.layout.cells;
+        let occupied_around = |index: usize| {
+            self.neighbours[index]
+                .iter()
+                .filter(|&&n| cells[n] == Cell::Occupied)
+                .count()
+        };
+        let next: Vec<Cell> = cells
+            .iter()
+            .enumerate()
+            .map(|(index, &cell)| match cell {
+                Cell::Empty if occupied_around(index) == 0 => Cell::Occupied,
+                Cell::Occupied if occupied_around(index) >= self.tolerance => Cell::Empty,
+                cell => cell,
+            })
+            .collect();
+        if next == self.layout.cells {
+            return false;
+        }
+        self.layout.cells = next;
+        self.generation += 1;
+        true
+    }
+
+    /// Steps until nothing changes and returns how many generations that took.
+    pub fn run(&mut self) -> usize {
+        self.run_with(|_, _| ())
+    }
+
+    /// Like `run`, calling `on_generation` with each new generation number and layout.
+    pub fn run_with<F: FnMut(usize, &Layout)>(&mut self, mut on_generation: F) -> usize {
+        while self.step() {
+            on_generation(self.generation, &self.layout);
+        }
+        self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "L.LL.LL.LL
+        LLLLLLL.LL
+        L.L.L..L..
+        LLLL.LL.LL
+        L.LL.LL.LL
+        L.LLLLL.LL
+        ..L.L.....
+        LLLLLLLLLL
+        L.LLLLLL.L
+        L.LLLLL.LL";
+
+    #[test]
+    fn it_should_settle_with_adjacent_seats() {
+        assert_eq!(day11_1(EXAMPLE), Ok(37));
+    }
+
+    #[test]
+    fn it_should_settle_with_line_of_sight() {
+        assert_eq!(day11_2(EXAMPLE), Ok(26));
+    }
+
+    #[test]
+    fn it_should_step_one_generation_at_a_time() {
+        let mut simulation = Simulation::new(Layout::parse(EXAMPLE).unwrap(), Rule::Adjacent);
+        simulation.step();
+        simulation.step();
+        assert_eq!(
+            simulation.layout().to_string(),
+            "#.LL.L#.##
+#LLLLLL.L#
+L.L.L..L..
+#LLL.LL.L#
+#.LL.LL.LL
+#.LLLL#.##
+..L.L.....
+#LLLLLLLL#
+#.LLLLLL.L
+#.#LLLL.##
+"
+        );
+        assert_eq!(simulation.run(), 5);
+    }
+
+    #[test]
+    fn it_should_look_past_the_floor() {
+        let layout = Layout::parse(
+            ".......#.
+            ...#.....
+            .#.......
+            .........
+            ..#L....#
+            ....#....
+            .........
+            #........
+            ...#.....",
+        )
+        .unwrap();
+        let seat = layout.index(4, 3);
+        assert_eq!(Rule::LineOfSight.neighbours(&layout)[seat].len(), 8);
+        assert_eq!(Rule::Adjacent.neighbours(&layout)[seat].len(), 2);
+
+        // the empty seat blocks the view of the occupied one behind it
+        let layout = Layout::parse(".............\n.L.L.#.#.#.#.\n.............").unwrap();
+        let seat = layout.index(1, 1);
+        assert_eq!(
+            Rule::LineOfSight.neighbours(&layout)[seat],
+            vec![layout.index(1, 3)]
+        );
+    }
+
+    #[test]
+    fn it_should_reject_ragged_rows() {
+        assert_eq!(
+            Layout::parse("L\nLLL\nL#"),
+            Err(LayoutError::Ragged {
+                line: 2,
+                width: 3,
+                expected: 1
+            })
+        );
+        assert_eq!(
+            Layout::parse("LLL\n\nLL").unwrap_err().to_string(),
+            "line 3: row is 2 wide, expected 3"
+        );
+    }
+
+    #[test]
+    fn it_should_print_every_generation() {
+        let out = day11_generations("L.L\nLLL", Rule::Adjacent).unwrap();
+        assert_eq!(
+            out,
+            "generation 0\nL.L\nLLL\n\ngeneration 1\n#.#\n###\n\ngeneration 2\n#.#\n#L#\n\n"
+        );
+    }
+
+    #[test]
+    fn it_should_reject_unknown_cells() {
+        assert_eq!(
+            Layout::parse("L.L\nL?L").unwrap_err().to_string(),
+            "line 2, column 2: expected '.', 'L' or '#', found '?'"
+        );
+        // blank lines still count
+        assert_eq!(
+            Layout::parse("\nL.L\n\nL?L").unwrap_err().to_string(),
+            "line 4, column 2: expected '.', 'L' or '#', found '?'"
+        );
+    }
+}
@@ -0,0 +1,333 @@
+/*
+Rain risk. Navigation instructions are parsed once into typed Instructions, turns become a number
+of quarter turns clockwise (anything that isn't a multiple of 90 degrees is a parse error), and two
+Ship models read them: one that moves itself along a heading, and one that moves a waypoint
+around and sails towards it.
+Positions are (east, north), so west and south are negative. They're i32 and every move is
+checked: a ship that sails off past i32::MAX is an error, not a wrapped around answer.
+*/
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+pub fn day12_1(input: &str) -> Result<i32, NavigationError> {
+    navigate(HeadingShip::default(), &parse_instructions(input)?)
+}
+
+pub fn day12_2(input: &str) -> Result<i32, NavigationError> {
+    navigate(WaypointShip::default(), &parse_instructions(input)?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// One unit in this direction, as (east, north).
+    fn unit(self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, 1),
+            Direction::East => (1, 0),
+            Direction::South => (0, -1),
+            Direction::West => (-1, 0),
+        }
+    }
+
+    fn turn(self, quarters: u8) -> Direction {
+        const CLOCKWISE: [Direction; 4] = [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ];
+        let at = CLOCKWISE.iter().position(|&d| d == self).unwrap();
+        CLOCKWISE[(at + quarters as usize) % 4]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Move(Direction, i32),
+    /// Quarter turns clockwise, 0 to 3 (so `L90` is 3)
+    Turn(u8),
+    Forward(i32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NavigationError {
+    Parse {
+        line: usize,
+        message: String,
+    },
+    /// The ship (or its waypoint) ended up further out than an i32 can say
+    Overflow,
+}
+
+impl Display for NavigationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NavigationError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            NavigationError::Overflow => write!(f, "the ship sailed too far to keep track of"),
+        }
+    }
+}
+
+impl Error for NavigationError {}
+
+impl FromStr for Instruction {
+    type Err = NavigationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |message: String| NavigationError::Parse { line: 1, message };
+        let s = s.trim();
+        let mut chars = s.chars();
+        let action = chars
+            .next()
+            .ok_or_else(|| error("empty instruction".to_string()))?;
+        let value: i32 = chars
+            .as_str()
+            .parse()
+            .map_err(|_| error(format!("invalid value in: {}", s)))?;
+        let turn = |degrees: i32| {
+            if degrees % 90 != 0 {
+                Err(error(format!(
+                    "turns have to be a multiple of 90 degrees, found: {}",
+                    s
+                )))
+            } else {
+                Ok(Instruction::Turn((degrees / 90).rem_euclid(4) as u8))
+            }
+        };
+        match action {
+            'N' => Ok(Instruction::Move(Direction::North, value)),
+            'S' => Ok(Instruction::Move(Direction::South, value)),
+            'E' => Ok(Instruction::Move(Direction::East, value)),
+            'W' => Ok(Instruction::Move(Direction::West, value)),
+            'L' => turn(
+                value
+                    .checked_neg()
+                    .ok_or_else(|| error(format!("turn is too far in: {}", s)))?,
+            ),
+            'R' => turn(value),
+            'F' => Ok(Instruction::Forward(value)),
+            _ => Err(error(format!("unknown action '{}' in: {}", action, s))),
+        }
+    }
+}
+
+fn parse_instructions(input: &str) -> Result<Vec<Instruction>, NavigationError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            line.parse().map_err(|e| match e {
+                NavigationError::Parse { message, .. } => NavigationError::Parse {
+                    line: idx + 1,
+                    message,
+                },
+                other => other,
+            })
+        })
+        .collect()
+}
+
+/// Turns (east, north) clockwise around the origin. Only i32::MIN has no opposite to turn into.
+fn rotate((east, north): (i32, i32), quarters: u8) -> Result<(i32, i32), NavigationError> {
+    (0..quarters).try_fold((east, north), |(east, north), _| {
+        Ok((north, east.checked_neg().ok_or(NavigationError::Overflow)?))
+    })
+}
+
+/// `from` moved `times` times by (east, north).
+fn travel(
+    from: (i32, i32),
+    (east, north): (i32, i32),
+    times: i32,
+) -> Result<(i32, i32), NavigationError> {
+    let step = |at: i32, by: i32| by.checked_mul(times).and_then(|by| at.checked_add(by));
+    match (step(from.0, east), step(from.1, north)) {
+        (Some(east), Some(north)) => Ok((east, north)),
+        _ => Err(NavigationError::Overflow),
+    }
+}
+
+pub trait Ship {
+    fn execute(&mut self, instruction: Instruction) -> Result<(), NavigationError>;
+
+    /// Where the ship is, as (east, north) from where it started.
+    fn position(&self) -> (i32, i32);
+
+    fn manhattan_distance(&self) -> Result<i32, NavigationError> {
+        let (east, north) = self.position();
+        east.checked_abs()
+            .zip(north.checked_abs())
+            .and_then(|(east, north)| east.checked_add(north))
+            .ok_or(NavigationError::Overflow)
+    }
+}
+
+/// Runs every instruction and says how far the ship ended up from the start.
+pub fn navigate<S: Ship>(
+    mut ship: S,
+    instructions: &[Instruction],
+) -> Result<i32, NavigationError> {
+    for &instruction in instructions {
+        ship.execute(instruction)?;
+    }
+    ship.manhattan_distance()
+}
+
+/// Moves and turns itself, starting out facing east.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadingShip {
+    position: (i32, i32),
+    heading: Direction,
+}
+
+impl Default for HeadingShip {
+    fn default() -> HeadingShip {
+        HeadingShip {
+            position: (0, 0),
+            heading: Direction::East,
+        }
+    }
+}
+
+impl Ship for HeadingShip {
+    fn execute(&mut self, instruction: Instruction) -> Result<(), NavigationError> {
+        let (direction, distance) = match instruction {
+            Instruction::Move(direction, distance) => (direction, distance),
+            Instruction::Forward(distance) => (self.heading, distance),
+            Instruction::Turn(quarters) => {
+                self.heading = self.heading.turn(quarters);
+                return Ok(());
+            }
+        };
+        self.position = travel(self.position, direction.unit(), distance)?;
+        Ok(())
+    }
+
+    fn position(&self) -> (i32, i32) {
+        self.position
+    }
+}
+
+/// N/S/E/W move the waypoint, turns rotate it around the ship, and forward sails to it that many
+/// times. The waypoint starts 10 east and 1 north of the ship.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaypointShip {
+    position: (i32, i32),
+    waypoint: (i32, i32),
+}
+
+impl Default for WaypointShip {
+    fn default() -> WaypointShip {
+        WaypointShip {
+            position: (0, 0),
+            waypoint: (10, 1),
+        }
+    }
+}
+
+impl Ship for WaypointShip {
+    fn execute(&mut self, instruction: Instruction) -> Result<(), NavigationError> {
+        match instruction {
+            Instruction::Move(direction, distance) => {
+                self.waypoint = travel(self.waypoint, direction.unit(), distance)?
+            }
+            Instruction::Turn(quarters) => self.waypoint = rotate(self.waypoint, quarters)?,
+            Instruction::Forward(times) => {
+                self.position = travel(self.position, self.waypoint, times)?
+            }
+        }
+        Ok(())
+    }
+
+    fn position(&self) -> (i32, i32) {
+        self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "F10
+        N3
+        F7
+        R90
+        F11";
+
+    #[test]
+    fn it_should_parse_typed_instructions() {
+        assert_eq!("N3".parse(), Ok(Instruction::Move(Direction::North, 3)));
+        assert_eq!("R90".parse(), Ok(Instruction::Turn(1)));
+        assert_eq!("L90".parse(), Ok(Instruction::Turn(3)));
+        assert_eq!("R360".parse(), Ok(Instruction::Turn(0)));
+        assert_eq!("F11".parse(), Ok(Instruction::Forward(11)));
+    }
+
+    #[test]
+    fn it_should_reject_odd_turns_and_bad_lines() {
+        let error = |input| parse_instructions(input).unwrap_err().to_string();
+        assert_eq!(
+            error("F10\nR45"),
+            "line 2: turns have to be a multiple of 90 degrees, found: R45"
+        );
+        assert_eq!(error("X10"), "line 1: unknown action 'X' in: X10");
+        assert_eq!(error("N3\n\nFten"), "line 3: invalid value in: Ften");
+        assert_eq!(
+            error("L-2147483648"),
+            "line 1: turn is too far in: L-2147483648"
+        );
+    }
+
+    #[test]
+    fn the_heading_ship_should_follow_the_example() {
+        let mut ship = HeadingShip::default();
+        for instruction in parse_instructions(EXAMPLE).unwrap() {
+            ship.execute(instruction).unwrap();
+        }
+        assert_eq!(ship.position(), (17, -8));
+        assert_eq!(ship.heading, Direction::South);
+        assert_eq!(day12_1(EXAMPLE), Ok(25));
+    }
+
+    #[test]
+    fn the_waypoint_ship_should_follow_the_example() {
+        let mut ship = WaypointShip::default();
+        for instruction in parse_instructions(EXAMPLE).unwrap() {
+            ship.execute(instruction).unwrap();
+        }
+        assert_eq!(ship.position(), (214, -72));
+        assert_eq!(ship.waypoint, (4, -10));
+        assert_eq!(day12_2(EXAMPLE), Ok(286));
+    }
+
+    #[test]
+    fn it_should_rotate_both_ways() {
+        assert_eq!(rotate((10, 4), 1), Ok((4, -10)));
+        assert_eq!(rotate((10, 4), 2), Ok((-10, -4)));
+        assert_eq!(rotate((10, 4), 3), Ok((-4, 10)));
+        assert_eq!(rotate((i32::MIN, 0), 1), Err(NavigationError::Overflow));
+        assert_eq!(Direction::East.turn(3), Direction::North);
+    }
+
+    #[test]
+    fn it_should_not_sail_past_i32() {
+        assert_eq!(day12_2("F300000000"), Err(NavigationError::Overflow));
+        assert_eq!(day12_1("E2147483647\nE1"), Err(NavigationError::Overflow));
+        // both fit, but not added together
+        assert_eq!(day12_1("E2147483647\nN1"), Err(NavigationError::Overflow));
+        assert_eq!(day12_1("W2147483647\nS1"), Err(NavigationError::Overflow));
+        assert_eq!(
+            NavigationError::Overflow.to_string(),
+            "the ship sailed too far to keep track of"
+        );
+    }
+}
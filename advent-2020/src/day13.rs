@@ -0,0 +1,220 @@
+/*
+Shuttle search.
+Part 1 is the bus with the shortest wait after we get to the stop.
+Part 2 wants a timestamp t where the bus at offset i leaves at t + i, i.e. t ≡ -i (mod id) for
+every bus. That's the Chinese Remainder Theorem: merge the congruences one at a time with the
+extended Euclidean algorithm. The ids aren't assumed to be coprime, merging just checks the two
+congruences agree. Everything is i128/u128, the products of real bus ids get big, and a few ids
+close to u64::MAX are enough to overflow even that, so the merge checks its multiplications.
+*/
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+pub fn day13_1(input: &str) -> Result<u64, ScheduleError> {
+    let notes = Notes::parse(input)?;
+    let (bus, wait) = notes.earliest_bus().ok_or(ScheduleError::NoBuses)?;
+    bus.checked_mul(wait).ok_or(ScheduleError::Overflow)
+}
+
+pub fn day13_2(input: &str) -> Result<u128, ScheduleError> {
+    earliest_timestamp(&Notes::parse(input)?.buses)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleError {
+    Parse(String),
+    NoBuses,
+    /// No timestamp works for this bus and the ones before it
+    Impossible {
+        offset: usize,
+        bus: u64,
+    },
+    /// The answer (or the period of the buses so far) doesn't fit in its integer type
+    Overflow,
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleError::Parse(message) => write!(f, "{}", message),
+            ScheduleError::NoBuses => write!(f, "there are no buses in service"),
+            ScheduleError::Impossible { offset, bus } => write!(
+                f,
+                "bus {} at offset {} can never line up with the buses before it",
+                bus, offset
+            ),
+            ScheduleError::Overflow => write!(f, "the bus ids are too big to work with"),
+        }
+    }
+}
+
+impl Error for ScheduleError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notes {
+    earliest: u64,
+    /// Bus ids by offset, None for an `x`
+    buses: Vec<Option<u64>>,
+}
+
+impl Notes {
+    /// The earliest timestamp on the first line, the comma separated buses on the second.
+    pub fn parse(input: &str) -> Result<Notes, ScheduleError> {
+        let mut lines = input.lines().map(str::trim).filter(|l| !l.is_empty());
+        let earliest = lines
+            .next()
+            .ok_or_else(|| ScheduleError::Parse("missing the earliest timestamp".to_string()))?;
+        let earliest = earliest
+            .parse()
+            .map_err(|_| ScheduleError::Parse(format!("invalid timestamp: {}", earliest)))?;
+        let buses = lines
+            .next()
+            .ok_or_else(|| ScheduleError::Parse("missing the bus list".to_string()))?;
+        Ok(Notes {
+            earliest,
+            buses: parse_buses(buses)?,
+        })
+    }
+
+    /// The bus that leaves first at or after `earliest`, and how long we wait for it.
+    pub fn earliest_bus(&self) -> Option<(u64, u64)> {
+        self.buses
+            .iter()
+            .flatten()
+            .map(|&bus| (bus, (bus - self.earliest % bus) % bus))
+            .min_by_key(|&(_, wait)| wait)
+    }
+}
+
+fn parse_buses(list: &str) -> Result<Vec<Option<u64>>, ScheduleError> {
+    list.split(',')
+        .map(str::trim)
+        .map(|bus| match bus {
+            "x" => Ok(None),
+            _ => match bus.parse() {
+                Ok(0) | Err(_) => Err(ScheduleError::Parse(format!("invalid bus id: {}", bus))),
+                Ok(id) => Ok(Some(id)),
+            },
+        })
+        .collect()
+}
+
+/// (gcd, x, y) with a*x + b*y = gcd
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+/// Merges t ≡ r1 (mod m1) and t ≡ r2 (mod m2) into one congruence, None if they never agree.
+fn merge(
+    (r1, m1): (i128, i128),
+    (r2, m2): (i128, i128),
+) -> Result<Option<(i128, i128)>, ScheduleError> {
+    let (g, p, _) = extended_gcd(m1, m2);
+    if (r2 - r1) % g != 0 {
+        return Ok(None);
+    }
+    let lcm = (m1 / g).checked_mul(m2).ok_or(ScheduleError::Overflow)?;
+    // t = r1 + m1 * k where m1 * k ≡ r2 - r1 (mod m2)
+    let k = ((r2 - r1) / g % (m2 / g))
+        .checked_mul(p)
+        .ok_or(ScheduleError::Overflow)?
+        .rem_euclid(m2 / g);
+    // k < m2 / g, so r1 + m1 * k < lcm and can't overflow
+    Ok(Some(((r1 + m1 * k).rem_euclid(lcm), lcm)))
+}
+
+/// The first timestamp where the bus at each offset leaves that many minutes after it.
+pub fn earliest_timestamp(buses: &[Option<u64>]) -> Result<u128, ScheduleError> {
+    let mut constraints = buses
+        .iter()
+        .enumerate()
+        .filter_map(|(offset, bus)| bus.map(|bus| (offset, bus)));
+    let first = constraints.next().ok_or(ScheduleError::NoBuses)?;
+    let congruence = |(offset, bus): (usize, u64)| {
+        let bus = i128::from(bus);
+        ((-(offset as i128)).rem_euclid(bus), bus)
+    };
+
+    let mut solution = congruence(first);
+    for (offset, bus) in constraints {
+        solution = merge(solution, congruence((offset, bus)))?
+            .ok_or(ScheduleError::Impossible { offset, bus })?;
+    }
+    Ok(solution.0 as u128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "939
+        7,13,x,x,59,x,31,19";
+
+    fn timestamp(buses: &str) -> u128 {
+        earliest_timestamp(&parse_buses(buses).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn it_should_find_the_earliest_bus() {
+        let notes = Notes::parse(EXAMPLE).unwrap();
+        assert_eq!(notes.earliest_bus(), Some((59, 5)));
+        assert_eq!(day13_1(EXAMPLE), Ok(295));
+    }
+
+    #[test]
+    fn it_should_solve_every_offset_example() {
+        assert_eq!(day13_2(EXAMPLE), Ok(1068781));
+        assert_eq!(timestamp("17,x,13,19"), 3417);
+        assert_eq!(timestamp("67,7,59,61"), 754018);
+        assert_eq!(timestamp("67,x,7,59,61"), 779210);
+        assert_eq!(timestamp("67,7,x,59,61"), 1261476);
+        assert_eq!(timestamp("1789,37,47,1889"), 1202161486);
+    }
+
+    #[test]
+    fn it_should_handle_ids_that_share_factors() {
+        // t ≡ 0 (mod 4) and t ≡ -2 ≡ 4 (mod 6) gives 4
+        assert_eq!(timestamp("4,x,6"), 4);
+        assert_eq!(
+            earliest_timestamp(&parse_buses("4,6").unwrap()),
+            Err(ScheduleError::Impossible { offset: 1, bus: 6 })
+        );
+    }
+
+    #[test]
+    fn it_should_not_overflow_with_large_ids() {
+        let buses = "999999937,x,999999929,x,x,999999893";
+        let t = timestamp(buses);
+        for (offset, bus) in [(0u128, 999999937u128), (2, 999999929), (5, 999999893)].iter() {
+            assert_eq!((t + offset) % bus, 0);
+        }
+        // two ids near u64::MAX with no common factor have a period past i128::MAX
+        assert_eq!(
+            day13_2("0\n18446744073709551557,18446744073709551533"),
+            Err(ScheduleError::Overflow)
+        );
+        // a wait of almost u64::MAX minutes for that bus
+        assert_eq!(
+            day13_1("1\n18446744073709551557"),
+            Err(ScheduleError::Overflow)
+        );
+    }
+
+    #[test]
+    fn it_should_report_bad_notes() {
+        assert_eq!(
+            day13_1("939\n7,y"),
+            Err(ScheduleError::Parse("invalid bus id: y".to_string()))
+        );
+        assert_eq!(day13_2("939\nx,x"), Err(ScheduleError::NoBuses));
+        assert_eq!(
+            day13_1("939"),
+            Err(ScheduleError::Parse("missing the bus list".to_string()))
+        );
+    }
+}
@@ -0,0 +1,286 @@
+/*
+Docking data. The program is `mask = ...` and `mem[a] = v` lines, and memory is 36 bit, mostly
+empty, so it's a HashMap from address to value.
+
+A mask is kept as three bit sets: the bits it sets to 1, the bits it sets to 0 and the `X` bits.
+Version 1 masks the value. Version 2 masks the address instead, and every X bit in it floats, so
+one write lands on 2^X addresses. The floating addresses are every subset of the X bits, walked
+with the `(subset - 1) & bits` trick. A mask with lots of X bits would write to millions of
+addresses, so more than MAX_FLOATING_BITS of them is an error instead.
+Addresses and values wider than 36 bits are parse errors, and the final sum is checked: it takes
+2^28 addresses full of 36 bit values to go past a u64, which is a lot of memory but not impossible.
+*/
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+const WIDTH: usize = 36;
+pub const MAX_FLOATING_BITS: u32 = 16;
+
+pub fn day14_1(input: &str) -> Result<u64, DockingError> {
+    run(&parse_program(input)?, Decoder::V1)
+}
+
+pub fn day14_2(input: &str) -> Result<u64, DockingError> {
+    run(&parse_program(input)?, Decoder::V2)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DockingError {
+    Parse {
+        line: usize,
+        message: String,
+    },
+    TooManyFloatingBits {
+        line: usize,
+        bits: u32,
+    },
+    /// What's left in memory adds up to more than a u64
+    Overflow,
+}
+
+impl Display for DockingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DockingError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            DockingError::TooManyFloatingBits { line, bits } => write!(
+                f,
+                "line {}: mask has {} floating bits, at most {} are allowed",
+                line, bits, MAX_FLOATING_BITS
+            ),
+            DockingError::Overflow => write!(f, "the values in memory add up to more than a u64"),
+        }
+    }
+}
+
+impl Error for DockingError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Mask {
+    ones: u64,
+    zeros: u64,
+    floating: u64,
+}
+
+impl Mask {
+    fn parse(mask: &str) -> Result<Mask, String> {
+        if mask.len() != WIDTH {
+            return Err(format!(
+                "mask should be {} bits, found {}: {}",
+                WIDTH,
+                mask.len(),
+                mask
+            ));
+        }
+        let mut parsed = Mask::default();
+        for (i, c) in mask.chars().enumerate() {
+            let bit = 1 << (WIDTH - 1 - i);
+            match c {
+                '1' => parsed.ones |= bit,
+                '0' => parsed.zeros |= bit,
+                'X' => parsed.floating |= bit,
+                _ => return Err(format!("invalid mask bit '{}' in: {}", c, mask)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn apply_to_value(&self, value: u64) -> u64 {
+        (value | self.ones) & !self.zeros
+    }
+
+    /// Every address a version 2 write to `address` lands on.
+    fn addresses(&self, address: u64) -> impl Iterator<Item = u64> {
+        let base = (address | self.ones) & !self.floating;
+        let floating = self.floating;
+        let mut subset = Some(floating);
+        std::iter::from_fn(move || {
+            let current = subset?;
+            subset = if current == 0 {
+                None
+            } else {
+                Some((current - 1) & floating)
+            };
+            Some(base | current)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line {
+    Mask(Mask),
+    Write { address: u64, value: u64 },
+}
+
+/// Each line with its line number.
+fn parse_program(input: &str) -> Result<Vec<(usize, Line)>, DockingError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(number, line)| {
+            parse_line(line)
+                .map(|parsed| (number, parsed))
+                .map_err(|message| DockingError::Parse {
+                    line: number,
+                    message,
+                })
+        })
+        .collect()
+}
+
+fn parse_line(line: &str) -> Result<Line, String> {
+    let (target, value) = line
+        .split_once('=')
+        .map(|(target, value)| (target.trim(), value.trim()))
+        .ok_or_else(|| format!("expected `mask = ...` or `mem[a] = v`, found: {}", line))?;
+    if target == "mask" {
+        return Ok(Line::Mask(Mask::parse(value)?));
+    }
+    let address = target
+        .strip_prefix("mem[")
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(|| format!("expected `mask` or `mem[a]`, found: {}", target))?;
+    Ok(Line::Write {
+        address: parse_word(address, "address")?,
+        value: parse_word(value, "value")?,
+    })
+}
+
+/// A number that fits in WIDTH bits.
+fn parse_word(s: &str, what: &str) -> Result<u64, String> {
+    let word: u64 = s.parse().map_err(|_| format!("invalid {}: {}", what, s))?;
+    if word >> WIDTH != 0 {
+        return Err(format!("{} is wider than {} bits: {}", what, WIDTH, s));
+    }
+    Ok(word)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decoder {
+    /// The mask changes the value
+    V1,
+    /// The mask changes the address, and X bits float
+    V2,
+}
+
+/// Runs the program and adds up everything left in memory.
+fn run(program: &[(usize, Line)], decoder: Decoder) -> Result<u64, DockingError> {
+    let mut memory: HashMap<u64, u64> = HashMap::new();
+    let mut mask = Mask::default();
+    for &(number, line) in program {
+        match line {
+            Line::Mask(new) => {
+                let bits = new.floating.count_ones();
+                if decoder == Decoder::V2 && bits > MAX_FLOATING_BITS {
+                    return Err(DockingError::TooManyFloatingBits { line: number, bits });
+                }
+                mask = new;
+            }
+            Line::Write { address, value } => match decoder {
+                Decoder::V1 => {
+                    memory.insert(address, mask.apply_to_value(value));
+                }
+                Decoder::V2 => {
+                    for address in mask.addresses(address) {
+                        memory.insert(address, value);
+                    }
+                }
+            },
+        }
+    }
+    sum(&memory)
+}
+
+fn sum(memory: &HashMap<u64, u64>) -> Result<u64, DockingError> {
+    memory
+        .values()
+        .try_fold(0u64, |sum, &value| sum.checked_add(value))
+        .ok_or(DockingError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_V1: &str = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
+        mem[8] = 11
+        mem[7] = 101
+        mem[8] = 0";
+
+    const EXAMPLE_V2: &str = "mask = 000000000000000000000000000000X1001X
+        mem[42] = 100
+        mask = 00000000000000000000000000000000X0XX
+        mem[26] = 1";
+
+    #[test]
+    fn it_should_mask_values() {
+        let mask = Mask::parse("XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X").unwrap();
+        assert_eq!(mask.apply_to_value(11), 73);
+        assert_eq!(mask.apply_to_value(101), 101);
+        assert_eq!(mask.apply_to_value(0), 64);
+        assert_eq!(day14_1(EXAMPLE_V1), Ok(165));
+    }
+
+    #[test]
+    fn it_should_float_addresses() {
+        let mask = Mask::parse("000000000000000000000000000000X1001X").unwrap();
+        let mut addresses: Vec<u64> = mask.addresses(42).collect();
+        addresses.sort_unstable();
+        assert_eq!(addresses, vec![26, 27, 58, 59]);
+        let mask = Mask::parse("00000000000000000000000000000000X0XX").unwrap();
+        assert_eq!(mask.addresses(26).count(), 8);
+        assert_eq!(day14_2(EXAMPLE_V2), Ok(208));
+    }
+
+    #[test]
+    fn it_should_refuse_masks_that_float_too_much() {
+        let input = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X\nmem[8] = 11";
+        assert_eq!(
+            day14_2(input),
+            Err(DockingError::TooManyFloatingBits { line: 1, bits: 34 })
+        );
+        // version 1 doesn't float anything, so the same mask is fine
+        assert_eq!(day14_1(input), Ok(73));
+    }
+
+    #[test]
+    fn it_should_report_bad_lines() {
+        let error = |input| day14_1(input).unwrap_err().to_string();
+        assert_eq!(
+            error("mask = 1X0"),
+            "line 1: mask should be 36 bits, found 3: 1X0"
+        );
+        assert_eq!(
+            error("mem[8] = 11\nmem[x] = 1"),
+            "line 2: invalid address: x"
+        );
+        assert_eq!(
+            error("reg[1] = 2"),
+            "line 1: expected `mask` or `mem[a]`, found: reg[1]"
+        );
+        assert_eq!(
+            error("mem[8] = 68719476735\nmem[9] = 68719476736"),
+            "line 2: value is wider than 36 bits: 68719476736"
+        );
+        assert_eq!(
+            error("mem[68719476736] = 1"),
+            "line 1: address is wider than 36 bits: 68719476736"
+        );
+    }
+
+    #[test]
+    fn it_should_not_overflow_the_sum() {
+        // a real program needs 2^28 addresses to get here, a few big values will do
+        let memory: HashMap<u64, u64> = [(0, u64::MAX - 1), (1, 1), (2, 1)]
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(sum(&memory), Err(DockingError::Overflow));
+        assert_eq!(
+            day14_2("mask = 000000000000000000000000000000000XXX\nmem[0] = 68719476735"),
+            Ok(8 * 68719476735)
+        );
+    }
+}
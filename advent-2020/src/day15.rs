@@ -0,0 +1,173 @@
+/*
+Rambunctious recitation, a Van Eck sequence. Each turn says 0 if the last number was new, or how
+many turns ago it was said before that.
+
+Only the last turn each number was said matters, and no number said during the game can be
+bigger than the number of turns, so that's a flat Vec<u32> indexed by number instead of a HashMap.
+30 million turns is a 120MB table though, and most big numbers are only ever said once, so a
+bitset of numbers already said (under 4MB, it stays in cache) answers "is this new?" without
+reading the big table. That takes about a third off part 2.
+Starting numbers can be anything, so the ones too big for the table go in a small HashMap on the
+side instead of growing the table to fit them.
+*/
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+pub const PART_1_TURNS: u32 = 2020;
+pub const PART_2_TURNS: u32 = 30_000_000;
+
+pub fn day15_1(input: &str) -> Result<u32, GameError> {
+    play(&parse_starting_numbers(input)?, PART_1_TURNS)
+}
+
+pub fn day15_2(input: &str) -> Result<u32, GameError> {
+    play(&parse_starting_numbers(input)?, PART_2_TURNS)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameError {
+    Parse(String),
+    NoStartingNumbers,
+    NoTurns,
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::Parse(number) => write!(f, "invalid starting number: {}", number),
+            GameError::NoStartingNumbers => write!(f, "the game needs starting numbers"),
+            GameError::NoTurns => write!(f, "the game needs at least one turn"),
+        }
+    }
+}
+
+impl Error for GameError {}
+
+fn parse_starting_numbers(input: &str) -> Result<Vec<u32>, GameError> {
+    input
+        .trim()
+        .split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().map_err(|_| GameError::Parse(n.to_string())))
+        .collect()
+}
+
+/// When each number was last said.
+struct Said {
+    last_turn: Vec<u32>,
+    /// One bit per number in `last_turn`, set once it's been said
+    seen: Vec<u64>,
+    /// Starting numbers too big for `last_turn`
+    big: HashMap<u32, u32>,
+}
+
+impl Said {
+    fn new(size: usize) -> Said {
+        Said {
+            last_turn: vec![0; size],
+            seen: vec![0; size / 64 + 1],
+            big: HashMap::new(),
+        }
+    }
+
+    /// Records `number` being said on `turn`, returning the turn it was said before that.
+    fn record(&mut self, number: u32, turn: u32) -> Option<u32> {
+        let number = number as usize;
+        if number >= self.last_turn.len() {
+            return self.big.insert(number as u32, turn);
+        }
+        let bit = 1 << (number % 64);
+        let word = &mut self.seen[number / 64];
+        if *word & bit == 0 {
+            *word |= bit;
+            self.last_turn[number] = turn;
+            None
+        } else {
+            Some(std::mem::replace(&mut self.last_turn[number], turn))
+        }
+    }
+}
+
+/// The number spoken on turn `turns` (turns count from 1).
+pub fn play(starting: &[u32], turns: u32) -> Result<u32, GameError> {
+    if turns == 0 {
+        return Err(GameError::NoTurns);
+    }
+    let (&first, rest) = starting.split_first().ok_or(GameError::NoStartingNumbers)?;
+    if turns as usize <= starting.len() {
+        return Ok(starting[turns as usize - 1]);
+    }
+
+    let mut said = Said::new(turns as usize);
+    let mut spoken = first;
+    // the previous number goes into the table only once the next one is worked out
+    for (turn, &n) in (1..).zip(rest) {
+        said.record(spoken, turn);
+        spoken = n;
+    }
+    for turn in starting.len() as u32..turns {
+        spoken = match said.record(spoken, turn) {
+            Some(before) => turn - before,
+            None => 0,
+        };
+    }
+    Ok(spoken)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_play_the_first_turns() {
+        let spoken: Vec<u32> = (1..=10)
+            .map(|turn| play(&[0, 3, 6], turn).unwrap())
+            .collect();
+        assert_eq!(spoken, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+    }
+
+    #[test]
+    fn it_should_find_the_2020th_number() {
+        assert_eq!(day15_1("0,3,6"), Ok(436));
+        assert_eq!(day15_1("1,3,2"), Ok(1));
+        assert_eq!(day15_1("2,1,3"), Ok(10));
+        assert_eq!(day15_1("1,2,3"), Ok(27));
+        assert_eq!(day15_1("2,3,1"), Ok(78));
+        assert_eq!(day15_1("3,2,1"), Ok(438));
+        assert_eq!(day15_1("3,1,2"), Ok(1836));
+    }
+
+    #[test]
+    fn it_should_find_the_30000000th_number() {
+        assert_eq!(day15_2("0,3,6"), Ok(175594));
+    }
+
+    // the other 6 games of 30 million turns take a while without optimisations, so:
+    // cargo test --release day15::tests::it_should_find_every_30000000th_number -- --ignored
+    #[test]
+    #[ignore]
+    fn it_should_find_every_30000000th_number() {
+        assert_eq!(day15_2("1,3,2"), Ok(2578));
+        assert_eq!(day15_2("2,1,3"), Ok(3544142));
+        assert_eq!(day15_2("1,2,3"), Ok(261214));
+        assert_eq!(day15_2("2,3,1"), Ok(6895259));
+        assert_eq!(day15_2("3,2,1"), Ok(18));
+        assert_eq!(day15_2("3,1,2"), Ok(362));
+    }
+
+    #[test]
+    fn it_should_handle_odd_games() {
+        assert_eq!(play(&[7], 1), Ok(7));
+        assert_eq!(play(&[7], 3), Ok(0));
+        assert_eq!(play(&[7], 4), Ok(1));
+        // starting numbers bigger than the number of turns stay out of the table
+        assert_eq!(play(&[100, 100], 4), Ok(0));
+        assert_eq!(play(&[u32::MAX, 5, u32::MAX], 5), Ok(0));
+        assert_eq!(play(&[u32::MAX, 5, u32::MAX], 4), Ok(2));
+        assert_eq!(play(&[], 5), Err(GameError::NoStartingNumbers));
+        assert_eq!(play(&[1], 0), Err(GameError::NoTurns));
+        assert_eq!(day15_1("1,x"), Err(GameError::Parse("x".to_string())));
+    }
+}
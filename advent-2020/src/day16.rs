@@ -0,0 +1,435 @@
+/*
+Ticket translation. The notes are field rules (a name and two ranges), our ticket, then the
+nearby tickets. Part 1 adds up the values on nearby tickets that fit no rule at all.
+
+Part 2 throws those tickets away and works out which field is at which position. Each position
+starts with every field whose rule fits all of its values, kept as a bit set (so at most 64
+fields), then two rules run until nothing changes: a position with a single field left claims it,
+and a field that only one position still allows goes to that position. If that stops with
+positions still undecided, the notes are ambiguous and that's an error rather than a guess.
+*/
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+const MAX_FIELDS: usize = 64;
+
+pub fn day16_1(input: &str) -> Result<u32, TicketError> {
+    Notes::parse(input)?.error_rate()
+}
+
+pub fn day16_2(input: &str) -> Result<u64, TicketError> {
+    let notes = Notes::parse(input)?;
+    let order = notes.field_order()?;
+    order
+        .iter()
+        .zip(&notes.mine)
+        .filter(|(field, _)| field.starts_with("departure"))
+        .try_fold(1u64, |product, (_, &value)| {
+            product.checked_mul(u64::from(value))
+        })
+        .ok_or(TicketError::Overflow)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TicketError {
+    Parse {
+        line: usize,
+        message: String,
+    },
+    /// Every field was ruled out for this position
+    NoField {
+        position: usize,
+    },
+    /// These positions could still be more than one field
+    Ambiguous {
+        positions: Vec<usize>,
+    },
+    /// The error rate or the departure product doesn't fit in its integer type
+    Overflow,
+}
+
+impl Display for TicketError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TicketError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            TicketError::NoField { position } => {
+                write!(f, "no field fits every value at position {}", position)
+            }
+            TicketError::Ambiguous { positions } => {
+                let positions: Vec<String> = positions.iter().map(usize::to_string).collect();
+                write!(
+                    f,
+                    "the notes don't decide the fields at positions {}",
+                    positions.join(", ")
+                )
+            }
+            TicketError::Overflow => write!(f, "the ticket values add up to too much"),
+        }
+    }
+}
+
+impl Error for TicketError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    name: String,
+    ranges: [(u32, u32); 2],
+}
+
+impl Rule {
+    /// `name: a-b or c-d`
+    fn parse(line: &str) -> Result<Rule, String> {
+        let (name, ranges) = line
+            .split_once(':')
+            .ok_or_else(|| format!("expected `name: a-b or c-d`, found: {}", line))?;
+        let range = |range: &str| -> Result<(u32, u32), String> {
+            let range = range.trim();
+            let invalid = || format!("invalid range '{}' in: {}", range, line);
+            let (low, high) = range.split_once('-').ok_or_else(invalid)?;
+            match (low.parse(), high.parse()) {
+                (Ok(low), Ok(high)) if low <= high => Ok((low, high)),
+                _ => Err(invalid()),
+            }
+        };
+        let (first, second) = ranges
+            .split_once(" or ")
+            .ok_or_else(|| format!("expected two ranges in: {}", line))?;
+        Ok(Rule {
+            name: name.trim().to_string(),
+            ranges: [range(first)?, range(second)?],
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn allows(&self, value: u32) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(low, high)| low <= value && value <= high)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notes {
+    rules: Vec<Rule>,
+    mine: Vec<u32>,
+    nearby: Vec<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Rules,
+    Mine,
+    Nearby,
+}
+
+impl Notes {
+    pub fn parse(input: &str) -> Result<Notes, TicketError> {
+        let mut rules = Vec::new();
+        let mut mine = None;
+        let mut nearby = Vec::new();
+        let mut section = Section::Rules;
+        for (idx, line) in input.lines().enumerate() {
+            let error = |message: String| TicketError::Parse {
+                line: idx + 1,
+                message,
+            };
+            let line = line.trim();
+            match line {
+                "" => continue,
+                "your ticket:" => section = Section::Mine,
+                "nearby tickets:" => section = Section::Nearby,
+                _ => match section {
+                    Section::Rules => {
+                        rules.push(Rule::parse(line).map_err(error)?);
+                        if rules.len() > MAX_FIELDS {
+                            return Err(error(format!("more than {} fields", MAX_FIELDS)));
+                        }
+                    }
+                    Section::Mine if mine.is_some() => {
+                        return Err(error("we only have one ticket".to_string()))
+                    }
+                    Section::Mine => mine = Some(parse_ticket(line, rules.len()).map_err(error)?),
+                    Section::Nearby => nearby.push(parse_ticket(line, rules.len()).map_err(error)?),
+                },
+            }
+        }
+        Ok(Notes {
+            mine: mine.ok_or_else(|| TicketError::Parse {
+                line: input.lines().count(),
+                message: "missing our ticket".to_string(),
+            })?,
+            rules,
+            nearby,
+        })
+    }
+
+    fn fits_any(&self, value: u32) -> bool {
+        self.rules.iter().any(|rule| rule.allows(value))
+    }
+
+    /// The sum of every nearby value that no rule allows.
+    pub fn error_rate(&self) -> Result<u32, TicketError> {
+        self.nearby
+            .iter()
+            .flatten()
+            .filter(|&&value| !self.fits_any(value))
+            .try_fold(0u32, |sum, &value| sum.checked_add(value))
+            .ok_or(TicketError::Overflow)
+    }
+
+    /// Nearby tickets where every value fits at least one rule.
+    pub fn valid_tickets(&self) -> impl Iterator<Item = &Vec<u32>> {
+        self.nearby
+            .iter()
+            .filter(move |ticket| ticket.iter().all(|&value| self.fits_any(value)))
+    }
+
+    /// The field name at each position on a ticket.
+    pub fn field_order(&self) -> Result<Vec<&str>, TicketError> {
+        let everything = if self.rules.len() == MAX_FIELDS {
+            u64::MAX
+        } else {
+            (1 << self.rules.len()) - 1
+        };
+        let mut candidates = vec![everything; self.rules.len()];
+        for ticket in self.valid_tickets() {
+            for (position, &value) in ticket.iter().enumerate() {
+                for (field, rule) in self.rules.iter().enumerate() {
+                    if !rule.allows(value) {
+                        candidates[position] &= !(1 << field);
+                    }
+                }
+            }
+        }
+        let fields = resolve(candidates)?;
+        Ok(fields
+            .into_iter()
+            .map(|field| self.rules[field].name())
+            .collect())
+    }
+}
+
+fn parse_ticket(line: &str, fields: usize) -> Result<Vec<u32>, String> {
+    let ticket = line
+        .split(',')
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid ticket value: {}", value))
+        })
+        .collect::<Result<Vec<u32>, String>>()?;
+    if ticket.len() != fields {
+        return Err(format!(
+            "expected {} values on a ticket, found {}",
+            fields,
+            ticket.len()
+        ));
+    }
+    Ok(ticket)
+}
+
+/// Narrows each position's set of possible fields down to one, returning the field for each.
+fn resolve(mut candidates: Vec<u64>) -> Result<Vec<usize>, TicketError> {
+    let mut assigned: Vec<Option<usize>> = vec![None; candidates.len()];
+    loop {
+        if let Some(position) = candidates.iter().position(|&c| c == 0) {
+            return Err(TicketError::NoField { position });
+        }
+        let open = |position: &usize| assigned[*position].is_none();
+        let single = (0..candidates.len())
+            .filter(open)
+            .find(|&position| candidates[position].count_ones() == 1)
+            .map(|position| (position, candidates[position].trailing_zeros() as usize));
+        // a field that's only possible in one open position has to go there
+        let hidden = || {
+            (0..candidates.len()).find_map(|field| {
+                let mut positions = (0..candidates.len())
+                    .filter(open)
+                    .filter(|&position| candidates[position] & (1 << field) != 0);
+                match (positions.next(), positions.next()) {
+                    (Some(position), None) => Some((position, field)),
+                    _ => None,
+                }
+            })
+        };
+        let (position, field) = match single.or_else(hidden) {
+            Some(found) => found,
+            None => break,
+        };
+        assigned[position] = Some(field);
+        for (other, c) in candidates.iter_mut().enumerate() {
+            *c = if other == position {
+                1 << field
+            } else {
+                *c & !(1 << field)
+            };
+        }
+    }
+    let undecided: Vec<usize> = (0..assigned.len())
+        .filter(|&position| assigned[position].is_none())
+        .collect();
+    if !undecided.is_empty() {
+        return Err(TicketError::Ambiguous {
+            positions: undecided,
+        });
+    }
+    Ok(assigned.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_1: &str = "class: 1-3 or 5-7
+        row: 6-11 or 33-44
+        seat: 13-40 or 45-50
+
+        your ticket:
+        7,1,14
+
+        nearby tickets:
+        7,3,47
+        40,4,50
+        55,2,20
+        38,6,12";
+
+    const EXAMPLE_2: &str = "class: 0-1 or 4-19
+        row: 0-5 or 8-19
+        seat: 0-13 or 16-19
+
+        your ticket:
+        11,12,13
+
+        nearby tickets:
+        3,9,18
+        15,1,5
+        5,14,9";
+
+    #[test]
+    fn it_should_find_the_error_rate() {
+        let notes = Notes::parse(EXAMPLE_1).unwrap();
+        assert_eq!(notes.error_rate(), Ok(71));
+        assert_eq!(
+            notes.valid_tickets().collect::<Vec<_>>(),
+            vec![&vec![7, 3, 47]]
+        );
+        assert_eq!(day16_1(EXAMPLE_1), Ok(71));
+    }
+
+    #[test]
+    fn it_should_work_out_the_field_order() {
+        let notes = Notes::parse(EXAMPLE_2).unwrap();
+        assert_eq!(notes.field_order(), Ok(vec!["row", "class", "seat"]));
+        // no departure fields, so the product is empty
+        assert_eq!(day16_2(EXAMPLE_2), Ok(1));
+    }
+
+    #[test]
+    fn it_should_multiply_the_departure_fields() {
+        let input = "departure time: 0-5 or 10-19
+            arrival: 0-19 or 30-40
+            departure gate: 0-1 or 4-19
+
+            your ticket:
+            11,12,13
+
+            nearby tickets:
+            30,2,1
+            99,99,99";
+        // only arrival takes 30, then only departure time is left that takes 2
+        assert_eq!(
+            Notes::parse(input).unwrap().field_order(),
+            Ok(vec!["arrival", "departure time", "departure gate"])
+        );
+        assert_eq!(day16_2(input), Ok(12 * 13));
+    }
+
+    #[test]
+    fn it_should_not_overflow() {
+        let input = "a: 1-1 or 2-2
+
+            your ticket:
+            1
+
+            nearby tickets:
+            4294967295
+            3";
+        assert_eq!(day16_1(input), Err(TicketError::Overflow));
+
+        let input = "departure a: 1-1 or 4294967290-4294967295
+            departure b: 2-2 or 4294967290-4294967295
+            departure c: 3-3 or 4294967290-4294967295
+
+            your ticket:
+            4294967295,4294967295,4294967295
+
+            nearby tickets:
+            1,2,3";
+        assert_eq!(day16_2(input), Err(TicketError::Overflow));
+    }
+
+    #[test]
+    fn it_should_refuse_ambiguous_notes() {
+        let input = "a: 1-5 or 7-8
+            b: 1-5 or 7-8
+            c: 10-10 or 20-20
+
+            your ticket:
+            1,2,10
+
+            nearby tickets:
+            3,4,20";
+        assert_eq!(
+            day16_2(input),
+            Err(TicketError::Ambiguous {
+                positions: vec![0, 1]
+            })
+        );
+    }
+
+    #[test]
+    fn it_should_notice_when_no_field_fits() {
+        let input = "a: 1-1 or 2-2
+            b: 5-5 or 6-6
+
+            your ticket:
+            1,5
+
+            nearby tickets:
+            1,1
+            5,5";
+        assert_eq!(day16_2(input), Err(TicketError::NoField { position: 0 }));
+        // both positions can only be a, so whichever loses out has nothing left
+        let input = "a: 1-1 or 9-9
+            b: 5-5 or 6-6
+
+            your ticket:
+            1,9
+
+            nearby tickets:
+            1,9";
+        assert_eq!(day16_2(input), Err(TicketError::NoField { position: 1 }));
+    }
+
+    #[test]
+    fn it_should_report_bad_notes() {
+        let error = |input| Notes::parse(input).unwrap_err().to_string();
+        assert_eq!(
+            error("class 1-3 or 5-7"),
+            "line 1: expected `name: a-b or c-d`, found: class 1-3 or 5-7"
+        );
+        assert_eq!(
+            error("class: 1-3 or 7-5"),
+            "line 1: invalid range '7-5' in: class: 1-3 or 7-5"
+        );
+        assert_eq!(
+            error("a: 1-3 or 5-7\nyour ticket:\n1\nnearby tickets:\n1,2"),
+            "line 5: expected 1 values on a ticket, found 2"
+        );
+        assert_eq!(error("a: 1-3 or 5-7\n"), "line 1: missing our ticket");
+    }
+}
@@ -0,0 +1,218 @@
+/*
+Conway cubes. An active cube stays active with 2 or 3 active neighbours, an inactive one turns on
+with exactly 3, which in 2 dimensions is plain Game of Life. Part 1 runs it in 3 dimensions and
+part 2 in 4, so there's one PocketDimension over N dimensional points (const generics) instead of
+a copy per dimension.
+
+The grid is infinite and mostly empty, so only the active cubes are kept, in a HashSet. A cycle has
+every active cube add one to each of its 3^N - 1 neighbours' counts; only cubes that end up with a
+count can be active next, so nothing outside the active region is ever looked at.
+*/
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+pub const CYCLES: usize = 6;
+
+pub fn day17_1(input: &str) -> Result<usize, CubeError> {
+    boot::<3>(input)
+}
+
+pub fn day17_2(input: &str) -> Result<usize, CubeError> {
+    boot::<4>(input)
+}
+
+fn boot<const N: usize>(input: &str) -> Result<usize, CubeError> {
+    let mut pocket = PocketDimension::<N>::parse(input)?;
+    pocket.run(CYCLES);
+    Ok(pocket.active())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CubeError {
+    line: usize,
+    column: usize,
+    found: char,
+}
+
+impl Display for CubeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected '.' or '#', found '{}'",
+            self.line, self.column, self.found
+        )
+    }
+}
+
+impl Error for CubeError {}
+
+pub type Point<const N: usize> = [i32; N];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PocketDimension<const N: usize> {
+    active: HashSet<Point<N>>,
+    /// Every step to a neighbour, all 3^N - 1 of them
+    offsets: Vec<Point<N>>,
+}
+
+impl<const N: usize> PocketDimension<N> {
+    /// The starting slice is 2D, so `PocketDimension::<1>` doesn't compile.
+    const AT_LEAST_2D: () = assert!(N >= 2, "the starting slice needs at least 2 dimensions");
+
+    /// The input is a 2D slice, `#` for active; every other coordinate is 0.
+    pub fn parse(input: &str) -> Result<PocketDimension<N>, CubeError> {
+        let () = Self::AT_LEAST_2D;
+        let mut active = HashSet::new();
+        let rows = input.lines().map(str::trim).filter(|line| !line.is_empty());
+        for (y, row) in rows.enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    '.' => {}
+                    '#' => {
+                        let mut point = [0; N];
+                        point[0] = x as i32;
+                        point[1] = y as i32;
+                        active.insert(point);
+                    }
+                    found => {
+                        return Err(CubeError {
+                            line: y + 1,
+                            column: x + 1,
+                            found,
+                        })
+                    }
+                }
+            }
+        }
+        Ok(PocketDimension {
+            active,
+            offsets: offsets(),
+        })
+    }
+
+    pub fn active(&self) -> usize {
+        self.active.len()
+    }
+
+    pub fn is_active(&self, point: &Point<N>) -> bool {
+        self.active.contains(point)
+    }
+
+    pub fn step(&mut self) {
+        // up to 3^N - 1 neighbours, which is already 728 at N = 6
+        let mut counts: HashMap<Point<N>, u32> = HashMap::new();
+        for cube in &self.active {
+            for offset in &self.offsets {
+                let mut neighbour = *cube;
+                for (coordinate, delta) in neighbour.iter_mut().zip(offset) {
+                    *coordinate += delta;
+                }
+                *counts.entry(neighbour).or_insert(0) += 1;
+            }
+        }
+        self.active = counts
+            .into_iter()
+            .filter(|(cube, count)| *count == 3 || (*count == 2 && self.is_active(cube)))
+            .map(|(cube, _)| cube)
+            .collect();
+    }
+
+    pub fn run(&mut self, cycles: usize) {
+        for _ in 0..cycles {
+            self.step();
+        }
+    }
+}
+
+/// Every point in {-1, 0, 1}^N except the origin.
+fn offsets<const N: usize>() -> Vec<Point<N>> {
+    let mut offsets = vec![[0; N]];
+    for axis in 0..N {
+        offsets = offsets
+            .into_iter()
+            .flat_map(|offset| {
+                (-1..=1).map(move |delta| {
+                    let mut offset = offset;
+                    offset[axis] = delta;
+                    offset
+                })
+            })
+            .collect();
+    }
+    offsets.retain(|offset| offset.iter().any(|&delta| delta != 0));
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = ".#.
+        ..#
+        ###";
+
+    #[test]
+    fn it_should_have_every_neighbour() {
+        assert_eq!(offsets::<2>().len(), 8);
+        assert_eq!(offsets::<3>().len(), 26);
+        assert_eq!(offsets::<4>().len(), 80);
+        let unique: HashSet<Point<3>> = offsets::<3>().into_iter().collect();
+        assert_eq!(unique.len(), 26);
+    }
+
+    #[test]
+    fn it_should_boot_in_3_dimensions() {
+        let mut pocket = PocketDimension::<3>::parse(EXAMPLE).unwrap();
+        pocket.step();
+        assert_eq!(pocket.active(), 11);
+        assert!(pocket.is_active(&[0, 1, -1]));
+        assert!(!pocket.is_active(&[1, 0, 0]));
+        pocket.run(CYCLES - 1);
+        assert_eq!(pocket.active(), 112);
+        assert_eq!(day17_1(EXAMPLE), Ok(112));
+    }
+
+    #[test]
+    fn it_should_boot_in_4_dimensions() {
+        let mut pocket = PocketDimension::<4>::parse(EXAMPLE).unwrap();
+        pocket.step();
+        assert_eq!(pocket.active(), 29);
+        assert_eq!(day17_2(EXAMPLE), Ok(848));
+    }
+
+    #[test]
+    fn it_should_be_game_of_life_in_2_dimensions() {
+        let mut blinker = PocketDimension::<2>::parse("...\n###\n...").unwrap();
+        blinker.step();
+        assert!([[1, 0], [1, 1], [1, 2]]
+            .iter()
+            .all(|p| blinker.is_active(p)));
+        assert_eq!(blinker.active(), 3);
+        blinker.step();
+        assert!([[0, 1], [1, 1], [2, 1]]
+            .iter()
+            .all(|p| blinker.is_active(p)));
+    }
+
+    #[test]
+    fn it_should_count_more_neighbours_than_fit_in_a_byte() {
+        // 259 active neighbours would wrap round to 3 in a u8 and switch the origin on
+        let mut pocket = PocketDimension::<6> {
+            active: offsets::<6>().into_iter().take(259).collect(),
+            offsets: offsets(),
+        };
+        pocket.step();
+        assert!(!pocket.is_active(&[0; 6]));
+    }
+
+    #[test]
+    fn it_should_reject_unknown_cubes() {
+        assert_eq!(
+            PocketDimension::<3>::parse(".#.\n.L.")
+                .unwrap_err()
+                .to_string(),
+            "line 2, column 2: expected '.' or '#', found 'L'"
+        );
+    }
+}
@@ -0,0 +1,411 @@
+/*
+Operation order. Each line is an expression with `+`, `*`, parentheses and whole numbers, and the
+answer is the sum of every line, evaluated with some unusual precedence rules: part 1 gives `+`
+and `*` the same precedence, part 2 does addition first.
+
+A line is split into tokens, then a Pratt parser builds an Expression tree from them with the
+precedence of each operator read from a Precedence table, so the two parts (and ordinary maths)
+are just different tables. Everything is left associative. An Expression prints fully
+parenthesised, which shows exactly how the table grouped it. Arithmetic is checked, and an
+overflow is reported at the operator that caused it.
+*/
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+pub fn day18_1(input: &str) -> Result<u64, ExpressionError> {
+    sum_lines(input, Precedence::EQUAL)
+}
+
+pub fn day18_2(input: &str) -> Result<u64, ExpressionError> {
+    sum_lines(input, Precedence::ADDITION_FIRST)
+}
+
+fn sum_lines(input: &str, precedence: Precedence) -> Result<u64, ExpressionError> {
+    let mut sum: u64 = 0;
+    for (idx, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value = parse(line, precedence)
+            .and_then(|expression| expression.evaluate())
+            .map_err(|e| ExpressionError { line: idx + 1, ..e })?;
+        sum = sum.checked_add(value).ok_or_else(|| ExpressionError {
+            line: idx + 1,
+            column: 1,
+            message: format!("adding {} to the sum so far ({}) overflows", value, sum),
+        })?;
+    }
+    Ok(sum)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl ExpressionError {
+    fn new(column: usize, message: String) -> ExpressionError {
+        ExpressionError {
+            line: 1,
+            column,
+            message,
+        }
+    }
+}
+
+impl Display for ExpressionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ExpressionError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Multiply,
+}
+
+impl Operator {
+    /// None if the answer doesn't fit in a u64.
+    fn apply(self, left: u64, right: u64) -> Option<u64> {
+        match self {
+            Operator::Add => left.checked_add(right),
+            Operator::Multiply => left.checked_mul(right),
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operator::Add => write!(f, "+"),
+            Operator::Multiply => write!(f, "*"),
+        }
+    }
+}
+
+/// How tightly each operator binds, higher first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Precedence {
+    pub add: u8,
+    pub multiply: u8,
+}
+
+impl Precedence {
+    /// Left to right, whatever the operator
+    pub const EQUAL: Precedence = Precedence {
+        add: 1,
+        multiply: 1,
+    };
+    pub const ADDITION_FIRST: Precedence = Precedence {
+        add: 2,
+        multiply: 1,
+    };
+
+    fn of(self, operator: Operator) -> u8 {
+        match operator {
+            Operator::Add => self.add,
+            Operator::Multiply => self.multiply,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Number(u64),
+    Operator(Operator),
+    Open,
+    Close,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Operator(operator) => write!(f, "'{}'", operator),
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+        }
+    }
+}
+
+/// Each token with the column it starts at.
+fn tokenize(line: &str) -> Result<Vec<(usize, Token)>, ExpressionError> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().enumerate().peekable();
+    while let Some((idx, c)) = chars.next() {
+        let column = idx + 1;
+        let token = match c {
+            ' ' | '\t' => continue,
+            '+' => Token::Operator(Operator::Add),
+            '*' => Token::Operator(Operator::Multiply),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '0'..='9' => {
+                let mut digits = c.to_string();
+                while let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_digit()) {
+                    digits.push(digit);
+                }
+                let number = digits.parse().map_err(|_| {
+                    ExpressionError::new(column, format!("number is too big: {}", digits))
+                })?;
+                Token::Number(number)
+            }
+            _ => {
+                return Err(ExpressionError::new(
+                    column,
+                    format!("unexpected character '{}'", c),
+                ))
+            }
+        };
+        tokens.push((column, token));
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    Number(u64),
+    /// The operator, the column it's at, and its operands
+    Binary(Operator, usize, Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    pub fn evaluate(&self) -> Result<u64, ExpressionError> {
+        match self {
+            Expression::Number(n) => Ok(*n),
+            Expression::Binary(operator, column, left, right) => {
+                let (left, right) = (left.evaluate()?, right.evaluate()?);
+                operator.apply(left, right).ok_or_else(|| {
+                    ExpressionError::new(
+                        *column,
+                        format!("{} {} {} overflows", left, operator, right),
+                    )
+                })
+            }
+        }
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expression::Number(n) => write!(f, "{}", n),
+            Expression::Binary(operator, _, left, right) => {
+                write!(f, "({} {} {})", left, operator, right)
+            }
+        }
+    }
+}
+
+/// Parses one line into an Expression, grouping operators by `precedence`.
+pub fn parse(line: &str, precedence: Precedence) -> Result<Expression, ExpressionError> {
+    let mut parser = Parser {
+        tokens: tokenize(line)?,
+        at: 0,
+        end: line.chars().count() + 1,
+        precedence,
+    };
+    let expression = parser.expression(0)?;
+    match parser.next() {
+        None => Ok(expression),
+        Some((column, token)) => Err(ExpressionError::new(
+            column,
+            format!("expected an operator, found {}", token),
+        )),
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    at: usize,
+    /// The column just past the end of the line, for errors about running out of tokens
+    end: usize,
+    precedence: Precedence,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(usize, Token)> {
+        self.tokens.get(self.at).copied()
+    }
+
+    fn next(&mut self) -> Option<(usize, Token)> {
+        let token = self.peek();
+        self.at += 1;
+        token
+    }
+
+    /// A number or a parenthesised expression.
+    fn operand(&mut self) -> Result<Expression, ExpressionError> {
+        match self.next() {
+            Some((_, Token::Number(n))) => Ok(Expression::Number(n)),
+            Some((_, Token::Open)) => {
+                let inside = self.expression(0)?;
+                match self.next() {
+                    Some((_, Token::Close)) => Ok(inside),
+                    Some((column, token)) => Err(ExpressionError::new(
+                        column,
+                        format!("expected ')', found {}", token),
+                    )),
+                    None => Err(ExpressionError::new(
+                        self.end,
+                        "expected ')', found the end of the line".to_string(),
+                    )),
+                }
+            }
+            Some((column, token)) => Err(ExpressionError::new(
+                column,
+                format!("expected a number or '(', found {}", token),
+            )),
+            None => Err(ExpressionError::new(
+                self.end,
+                "expected a number or '(', found the end of the line".to_string(),
+            )),
+        }
+    }
+
+    /// Keeps folding operators into the left hand side while they bind tighter than
+    /// `min_binding`. Each operator binds its right hand side one tighter than itself, which
+    /// is what makes a run of equal operators left associative.
+    /// Binding powers are u16, so doubling any u8 precedence (and adding one) still fits.
+    fn expression(&mut self, min_binding: u16) -> Result<Expression, ExpressionError> {
+        let mut left = self.operand()?;
+        while let Some((column, Token::Operator(operator))) = self.peek() {
+            let binding = u16::from(self.precedence.of(operator)) * 2;
+            if binding < min_binding {
+                break;
+            }
+            self.next();
+            let right = self.expression(binding + 1)?;
+            left = Expression::Binary(operator, column, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each example with its answer in part 1, then part 2.
+    const EXAMPLES: [(&str, u64, u64); 6] = [
+        ("1 + 2 * 3 + 4 * 5 + 6", 71, 231),
+        ("1 + (2 * 3) + (4 * (5 + 6))", 51, 51),
+        ("2 * 3 + (4 * 5)", 26, 46),
+        ("5 + (8 * 3 + 9 + 3 * 4 * 3)", 437, 1445),
+        ("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))", 12240, 669060),
+        (
+            "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2",
+            13632,
+            23340,
+        ),
+    ];
+
+    fn evaluate(line: &str, precedence: Precedence) -> u64 {
+        parse(line, precedence).unwrap().evaluate().unwrap()
+    }
+
+    #[test]
+    fn it_should_evaluate_left_to_right() {
+        for &(line, expected, _) in EXAMPLES.iter() {
+            assert_eq!(evaluate(line, Precedence::EQUAL), expected, "{}", line);
+        }
+        let all: Vec<&str> = EXAMPLES.iter().map(|&(line, _, _)| line).collect();
+        assert_eq!(
+            day18_1(&all.join("\n")),
+            Ok(26 + 437 + 12240 + 13632 + 71 + 51)
+        );
+    }
+
+    #[test]
+    fn it_should_evaluate_addition_first() {
+        for &(line, _, expected) in EXAMPLES.iter() {
+            assert_eq!(
+                evaluate(line, Precedence::ADDITION_FIRST),
+                expected,
+                "{}",
+                line
+            );
+        }
+        assert_eq!(day18_2("1 + 2 * 3\n2 * 3 + 4"), Ok(9 + 14));
+    }
+
+    #[test]
+    fn it_should_follow_any_precedence_table() {
+        let school = Precedence {
+            add: 1,
+            multiply: 2,
+        };
+        assert_eq!(evaluate("1 + 2 * 3", school), 7);
+        assert_eq!(evaluate("2 * 3 + 4", school), 10);
+        assert_eq!(evaluate("(1 + 2) * 3", school), 9);
+        // the highest precedence a table can give still binds tighter than the rest
+        let highest = Precedence {
+            add: 1,
+            multiply: u8::MAX,
+        };
+        assert_eq!(evaluate("1 + 2 * 3 + 4", highest), 11);
+    }
+
+    #[test]
+    fn it_should_report_overflow() {
+        let error = |input| day18_2(input).unwrap_err().to_string();
+        assert_eq!(
+            error("1\n2 * (18446744073709551615 + 1)"),
+            "line 2, column 27: 18446744073709551615 + 1 overflows"
+        );
+        assert_eq!(
+            error("4294967296 * 4294967296"),
+            "line 1, column 12: 4294967296 * 4294967296 overflows"
+        );
+        assert_eq!(
+            error("1\n\n18446744073709551615"),
+            "line 3, column 1: adding 18446744073709551615 to the sum so far (1) overflows"
+        );
+    }
+
+    #[test]
+    fn it_should_print_the_grouping() {
+        let print = |line, precedence| parse(line, precedence).unwrap().to_string();
+        assert_eq!(print("1 + 2 * 3", Precedence::EQUAL), "((1 + 2) * 3)");
+        assert_eq!(
+            print("2 * 3 + 4", Precedence::ADDITION_FIRST),
+            "(2 * (3 + 4))"
+        );
+        assert_eq!(
+            print("2 * 3 + (4 * 5)", Precedence::ADDITION_FIRST),
+            "(2 * (3 + (4 * 5)))"
+        );
+        assert_eq!(print("((42))", Precedence::EQUAL), "42");
+    }
+
+    #[test]
+    fn it_should_report_bad_expressions() {
+        let error = |input| day18_1(input).unwrap_err().to_string();
+        assert_eq!(
+            error("1 +"),
+            "line 1, column 4: expected a number or '(', found the end of the line"
+        );
+        assert_eq!(
+            error("1\n(1 + 2"),
+            "line 2, column 7: expected ')', found the end of the line"
+        );
+        assert_eq!(
+            error("1 2"),
+            "line 1, column 3: expected an operator, found 2"
+        );
+        assert_eq!(
+            error("1 + )"),
+            "line 1, column 5: expected a number or '(', found ')'"
+        );
+        assert_eq!(error("1 - 2"), "line 1, column 3: unexpected character '-'");
+    }
+}
@@ -0,0 +1,401 @@
+/*
+Monster messages. The rules are a grammar that only turns up with the input, so unlike the bags
+there's no pest grammar to derive: the numbered rules are parsed into a Grammar at runtime and
+messages are matched against rule 0 by backtracking.
+
+Matching a rule at some position gives every position it could end at, and a sequence of rules
+feeds each of those into the next rule. Keeping all the ends instead of the first one is what
+makes the looping rules from part 2 work (`8: 42 | 42 8` and `11: 42 31 | 42 11 31`): how many
+times 8 repeats only becomes clear once 11 has had its go. Those loops always eat a character
+before going round again so they stop by themselves. A rule that comes back to itself without
+eating anything (left recursion, like `0: 0 1 | 1`) would never stop, so the grammar is rejected
+before anything is matched. Every rule eats at least one character, so that's any rule that can
+reach itself by going through the first rule of an alternative, over and over.
+The ends of each (rule, position) are remembered for the message being matched, since the
+looping rules ask for the same ones again and again.
+*/
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+pub fn day19_1(input: &str) -> Result<usize, MessageError> {
+    let (grammar, messages) = parse_input(input)?;
+    Ok(grammar.count_matches(&messages))
+}
+
+pub fn day19_2(input: &str) -> Result<usize, MessageError> {
+    let (mut grammar, messages) = parse_input(input)?;
+    grammar.replace("8: 42 | 42 8")?;
+    grammar.replace("11: 42 31 | 42 11 31")?;
+    Ok(grammar.count_matches(&messages))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageError {
+    Parse {
+        line: usize,
+        message: String,
+    },
+    UndefinedRule(usize),
+    /// The rule can come back to itself without matching a character
+    LeftRecursion(usize),
+}
+
+impl Display for MessageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MessageError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            MessageError::UndefinedRule(rule) => {
+                write!(f, "rule {} is used but never defined", rule)
+            }
+            MessageError::LeftRecursion(rule) => {
+                write!(f, "rule {} is left recursive", rule)
+            }
+        }
+    }
+}
+
+impl Error for MessageError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rule {
+    Char(char),
+    /// Any one of these sequences of rules
+    Alternatives(Vec<Vec<usize>>),
+}
+
+/// `id: "c"` or `id: 1 2 | 3 4`
+fn parse_rule(line: &str) -> Result<(usize, Rule), String> {
+    let (id, body) = line
+        .split_once(':')
+        .ok_or_else(|| format!("expected `id: rule`, found: {}", line))?;
+    let id = id
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid rule id: {}", id))?;
+    let body = body.trim();
+    if let Some(quoted) = body.strip_prefix('"').and_then(|b| b.strip_suffix('"')) {
+        let mut chars = quoted.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok((id, Rule::Char(c))),
+            _ => Err(format!("expected a single character, found: {}", body)),
+        };
+    }
+    let alternatives = body
+        .split('|')
+        .map(|sequence| {
+            let sequence = sequence
+                .split_whitespace()
+                .map(|rule| {
+                    rule.parse()
+                        .map_err(|_| format!("invalid rule reference '{}' in: {}", rule, line))
+                })
+                .collect::<Result<Vec<usize>, String>>()?;
+            if sequence.is_empty() {
+                Err(format!("empty alternative in: {}", line))
+            } else {
+                Ok(sequence)
+            }
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((id, Rule::Alternatives(alternatives)))
+}
+
+/// The rules up to the first blank line, then the messages.
+fn parse_input(input: &str) -> Result<(Grammar, Vec<&str>), MessageError> {
+    let mut lines = input.lines().map(str::trim).enumerate();
+    let mut rules = HashMap::new();
+    for (idx, line) in lines.by_ref() {
+        if line.is_empty() {
+            break;
+        }
+        let error = |message| MessageError::Parse {
+            line: idx + 1,
+            message,
+        };
+        let (id, rule) = parse_rule(line).map_err(error)?;
+        if rules.insert(id, rule).is_some() {
+            return Err(error(format!("rule {} is defined twice", id)));
+        }
+    }
+    let grammar = Grammar { rules };
+    grammar.check()?;
+    let messages = lines
+        .map(|(_, line)| line)
+        .filter(|line| !line.is_empty())
+        .collect();
+    Ok((grammar, messages))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grammar {
+    rules: HashMap<usize, Rule>,
+}
+
+impl Grammar {
+    /// Every rule has to be defined, and none left recursive, before anything is matched.
+    fn check(&self) -> Result<(), MessageError> {
+        if !self.rules.contains_key(&0) {
+            return Err(MessageError::UndefinedRule(0));
+        }
+        for rule in self.rules.values() {
+            if let Rule::Alternatives(alternatives) = rule {
+                if let Some(&undefined) = alternatives
+                    .iter()
+                    .flatten()
+                    .find(|id| !self.rules.contains_key(id))
+                {
+                    return Err(MessageError::UndefinedRule(undefined));
+                }
+            }
+        }
+        let mut ids: Vec<usize> = self.rules.keys().copied().collect();
+        ids.sort_unstable();
+        match ids.into_iter().find(|&id| self.is_left_recursive(id)) {
+            Some(id) => Err(MessageError::LeftRecursion(id)),
+            None => Ok(()),
+        }
+    }
+
+    /// The rules an alternative of `rule` starts with.
+    fn first_rules(&self, rule: usize) -> impl Iterator<Item = usize> + '_ {
+        let alternatives: &[Vec<usize>] = match &self.rules[&rule] {
+            Rule::Char(_) => &[],
+            Rule::Alternatives(alternatives) => alternatives,
+        };
+        alternatives.iter().map(|sequence| sequence[0])
+    }
+
+    /// Whether `rule` can get back to itself through first rules only.
+    fn is_left_recursive(&self, rule: usize) -> bool {
+        let mut seen = HashSet::new();
+        let mut stack: Vec<usize> = self.first_rules(rule).collect();
+        while let Some(next) = stack.pop() {
+            if next == rule {
+                return true;
+            }
+            if seen.insert(next) {
+                stack.extend(self.first_rules(next));
+            }
+        }
+        false
+    }
+
+    /// Swaps in a new definition for a rule, written the same way as in the input.
+    pub fn replace(&mut self, line: &str) -> Result<(), MessageError> {
+        let (id, rule) =
+            parse_rule(line).map_err(|message| MessageError::Parse { line: 1, message })?;
+        self.rules.insert(id, rule);
+        self.check()
+    }
+
+    /// Whether all of `message` matches rule 0.
+    pub fn matches(&self, message: &str) -> bool {
+        let message: Vec<char> = message.chars().collect();
+        let mut matcher = Matcher {
+            grammar: self,
+            message: &message,
+            known: HashMap::new(),
+        };
+        matcher.ends(0, 0).contains(&message.len())
+    }
+
+    fn count_matches(&self, messages: &[&str]) -> usize {
+        messages
+            .iter()
+            .filter(|message| self.matches(message))
+            .count()
+    }
+}
+
+struct Matcher<'a> {
+    grammar: &'a Grammar,
+    message: &'a [char],
+    /// The ends already worked out for each (rule, position)
+    known: HashMap<(usize, usize), Vec<usize>>,
+}
+
+impl Matcher<'_> {
+    /// Every position where `rule` could finish if it starts at `at`. The grammar has no left
+    /// recursion, so a rule never comes back to the same position while it's still being matched.
+    fn ends(&mut self, rule: usize, at: usize) -> Vec<usize> {
+        if let Some(ends) = self.known.get(&(rule, at)) {
+            return ends.clone();
+        }
+        let ends = match &self.grammar.rules[&rule] {
+            Rule::Char(c) => match self.message.get(at) {
+                Some(found) if found == c => vec![at + 1],
+                _ => Vec::new(),
+            },
+            Rule::Alternatives(alternatives) => {
+                let mut ends = Vec::new();
+                for sequence in alternatives {
+                    let mut positions = vec![at];
+                    for &next in sequence {
+                        let mut after = Vec::new();
+                        for position in positions {
+                            after.extend(self.ends(next, position));
+                        }
+                        after.sort_unstable();
+                        after.dedup();
+                        positions = after;
+                    }
+                    ends.extend(positions);
+                }
+                ends
+            }
+        };
+        self.known.insert((rule, at), ends.clone());
+        ends
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_1: &str = "0: 4 1 5
+        1: 2 3 | 3 2
+        2: 4 4 | 5 5
+        3: 4 5 | 5 4
+        4: \"a\"
+        5: \"b\"
+
+        ababbb
+        bababa
+        abbbab
+        aaabbb
+        aaaabbb";
+
+    const EXAMPLE_2: &str = "42: 9 14 | 10 1
+        9: 14 27 | 1 26
+        10: 23 14 | 28 1
+        1: \"a\"
+        11: 42 31
+        5: 1 14 | 15 1
+        19: 14 1 | 14 14
+        12: 24 14 | 19 1
+        16: 15 1 | 14 14
+        31: 14 17 | 1 13
+        6: 14 14 | 1 14
+        2: 1 24 | 14 4
+        0: 8 11
+        13: 14 3 | 1 12
+        15: 1 | 14
+        17: 14 2 | 1 7
+        23: 25 1 | 22 14
+        28: 16 1
+        4: 1 1
+        20: 14 14 | 1 15
+        3: 5 14 | 16 1
+        27: 1 6 | 14 18
+        14: \"b\"
+        21: 14 1 | 1 14
+        25: 1 1 | 1 14
+        22: 14 14
+        8: 42
+        26: 14 22 | 1 20
+        18: 15 15
+        7: 14 5 | 1 21
+        24: 14 1
+
+        abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa
+        bbabbbbaabaabba
+        babbbbaabbbbbabbbbbbaabaaabaaa
+        aaabbbbbbaaaabaababaabababbabaaabbababababaaa
+        bbbbbbbaaaabbbbaaabbabaaa
+        bbbababbbbaaaaaaaabbababaaababaabab
+        ababaaaaaabaaab
+        ababaaaaabbbaba
+        baabbaaaabbaaaababbaababb
+        abbbbabbbbaaaababbbbbbaaaababb
+        aaaaabbaabaaaaababaa
+        aaaabbaaaabbaaa
+        aaaabbaabbaaaaaaabbbabbbaaabbaabaaa
+        babaaabbbaaabaababbaabababaaab
+        aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba";
+
+    #[test]
+    fn it_should_match_the_whole_message() {
+        let (grammar, messages) = parse_input(EXAMPLE_1).unwrap();
+        let matching: Vec<&str> = messages
+            .into_iter()
+            .filter(|m| grammar.matches(m))
+            .collect();
+        // aaaabbb matches up to its last character, which isn't enough
+        assert_eq!(matching, vec!["ababbb", "abbbab"]);
+        assert_eq!(day19_1(EXAMPLE_1), Ok(2));
+    }
+
+    #[test]
+    fn it_should_match_with_looping_rules() {
+        assert_eq!(day19_1(EXAMPLE_2), Ok(3));
+        assert_eq!(day19_2(EXAMPLE_2), Ok(12));
+        let (mut grammar, messages) = parse_input(EXAMPLE_2).unwrap();
+        grammar.replace("8: 42 | 42 8").unwrap();
+        grammar.replace("11: 42 31 | 42 11 31").unwrap();
+        let failing: Vec<&str> = messages
+            .into_iter()
+            .filter(|m| !grammar.matches(m))
+            .collect();
+        assert_eq!(
+            failing,
+            vec![
+                "abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa",
+                "aaaabbaaaabbaaa",
+                "babaaabbbaaabaababbaabababaaab"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_reject_left_recursion() {
+        assert_eq!(
+            day19_1("0: 0 1 | 1\n1: \"a\"\n\na"),
+            Err(MessageError::LeftRecursion(0))
+        );
+        // 1 comes back to itself through 2, rule 0 only leads into the loop
+        assert_eq!(
+            day19_1("0: 1\n1: 3 | 2 3\n2: 1 3\n3: \"a\"\n\na"),
+            Err(MessageError::LeftRecursion(1))
+        );
+        let (mut grammar, _) = parse_input("0: 1\n1: \"a\"").unwrap();
+        assert_eq!(grammar.replace("1: 0"), Err(MessageError::LeftRecursion(0)));
+        assert_eq!(
+            MessageError::LeftRecursion(0).to_string(),
+            "rule 0 is left recursive"
+        );
+        // going round after eating a character is fine
+        let (grammar, _) = parse_input("0: 1 | 1 0\n1: \"a\"").unwrap();
+        assert!(grammar.matches("aaa"));
+        assert!(!grammar.matches("aab"));
+    }
+
+    #[test]
+    fn it_should_report_bad_rules() {
+        assert_eq!(
+            day19_1("0: 1 2\n1: \"a\"\n\na"),
+            Err(MessageError::UndefinedRule(2))
+        );
+        assert_eq!(
+            day19_1("1: \"a\"\n\na"),
+            Err(MessageError::UndefinedRule(0))
+        );
+        // part 2 needs rules 42 and 31 to exist
+        assert_eq!(day19_2(EXAMPLE_1), Err(MessageError::UndefinedRule(42)));
+        let error = |input| day19_1(input).unwrap_err().to_string();
+        assert_eq!(
+            error("0: 1\n1: \"ab\""),
+            "line 2: expected a single character, found: \"ab\""
+        );
+        assert_eq!(
+            error("0: 1 x"),
+            "line 1: invalid rule reference 'x' in: 0: 1 x"
+        );
+        assert_eq!(error("0: 1 |"), "line 1: empty alternative in: 0: 1 |");
+        assert_eq!(
+            error("0: 1\n1: \"a\"\n1: \"b\""),
+            "line 3: rule 1 is defined twice"
+        );
+    }
+}
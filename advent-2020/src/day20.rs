@@ -0,0 +1,532 @@
+/*
+Jurassic jigsaw. The tiles make a square image, each one rotated or flipped some unknown way.
+
+Every edge is only ever shared by the two tiles that go together, so indexing tiles by their
+canonical edges is enough to put the image together without searching: the corners are the tiles
+with two edges nobody else has, one of them goes top left turned so those edges face out, and
+every other tile is the one sharing an edge with the tile to its left (or above it, at the start
+of a row), turned whichever of its 8 ways makes the edges line up.
+
+The borders come off every tile, and the sea monsters are searched for in all 8 orientations of
+the image. The water roughness is whatever `#` isn't part of a monster.
+*/
+mod tile;
+
+pub use tile::{Grid, Side, Tile};
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use tile::{MAX_TILE_SIZE, SIDES};
+
+pub const SEA_MONSTER: [&str; 3] = [
+    "                  # ",
+    "#    ##    ##    ###",
+    " #  #  #  #  #  #   ",
+];
+
+pub fn day20_1(input: &str) -> Result<u64, JigsawError> {
+    let tiles = parse_tiles(input)?;
+    let jigsaw = Jigsaw::new(&tiles)?;
+    jigsaw
+        .corners()?
+        .iter()
+        .try_fold(1u64, |product, &i| product.checked_mul(tiles[i].id))
+        .ok_or(JigsawError::Overflow)
+}
+
+pub fn day20_2(input: &str) -> Result<usize, JigsawError> {
+    let tiles = parse_tiles(input)?;
+    let image = Jigsaw::new(&tiles)?.assemble()?;
+    roughness(&image).ok_or(JigsawError::NoMonsters)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JigsawError {
+    Parse {
+        line: usize,
+        message: String,
+    },
+    /// The tiles can't make a square image
+    NotSquare(usize),
+    /// More than two tiles have this edge, so there's more than one way to go
+    SharedEdge(Vec<u64>),
+    Corners(usize),
+    /// Nothing fits at this spot in the image
+    NoFit {
+        row: usize,
+        column: usize,
+    },
+    NoMonsters,
+    /// The corner ids multiply to more than a u64
+    Overflow,
+}
+
+impl Display for JigsawError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JigsawError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            JigsawError::NotSquare(tiles) => write!(f, "{} tiles can't make a square", tiles),
+            JigsawError::SharedEdge(ids) => {
+                let ids: Vec<String> = ids.iter().map(u64::to_string).collect();
+                write!(f, "tiles {} all share an edge", ids.join(", "))
+            }
+            JigsawError::Corners(found) => write!(f, "expected 4 corner tiles, found {}", found),
+            JigsawError::NoFit { row, column } => {
+                write!(f, "no tile fits at row {}, column {}", row, column)
+            }
+            JigsawError::NoMonsters => write!(f, "there are no sea monsters in the image"),
+            JigsawError::Overflow => write!(f, "the corner tile ids multiply to too much"),
+        }
+    }
+}
+
+impl Error for JigsawError {}
+
+/// `Tile <id>:` then the rows of pixels, for each tile.
+fn parse_tiles(input: &str) -> Result<Vec<Tile>, JigsawError> {
+    // each tile's header line, id and rows
+    let mut blocks: Vec<(usize, u64, Vec<&str>)> = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        let error = |message: String| JigsawError::Parse {
+            line: idx + 1,
+            message,
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix("Tile ") {
+            let id = header
+                .strip_suffix(':')
+                .and_then(|id| id.parse().ok())
+                .ok_or_else(|| error(format!("invalid tile header: {}", line)))?;
+            blocks.push((idx + 1, id, Vec::new()));
+        } else {
+            match blocks.last_mut() {
+                Some((_, _, rows)) => rows.push(line),
+                None => return Err(error("pixels before the first tile header".to_string())),
+            }
+        }
+    }
+
+    let tiles = blocks
+        .into_iter()
+        .map(|(line, id, rows)| {
+            let error = |message: String| JigsawError::Parse {
+                line,
+                message: format!("tile {}: {}", id, message),
+            };
+            if rows.len() < 2 || rows.len() > MAX_TILE_SIZE {
+                return Err(error(format!(
+                    "tiles have to be 2 to {} pixels across, found {}",
+                    MAX_TILE_SIZE,
+                    rows.len()
+                )));
+            }
+            let grid = Grid::parse(&rows).map_err(error)?;
+            Ok(Tile { id, grid })
+        })
+        .collect::<Result<Vec<Tile>, JigsawError>>()?;
+    if let Some(odd) = tiles.iter().find(|t| t.grid.size() != tiles[0].grid.size()) {
+        return Err(JigsawError::Parse {
+            line: 1,
+            message: format!(
+                "tile {} is {} pixels across, the others are {}",
+                odd.id,
+                odd.grid.size(),
+                tiles[0].grid.size()
+            ),
+        });
+    }
+    Ok(tiles)
+}
+
+struct Jigsaw<'a> {
+    tiles: &'a [Tile],
+    /// Tiles per side of the image
+    across: usize,
+    /// Each canonical edge and the tiles that have it
+    edges: HashMap<u32, Vec<usize>>,
+}
+
+impl<'a> Jigsaw<'a> {
+    fn new(tiles: &'a [Tile]) -> Result<Jigsaw<'a>, JigsawError> {
+        let across = (1..=tiles.len())
+            .find(|across| across * across >= tiles.len())
+            .filter(|across| across * across == tiles.len())
+            .ok_or(JigsawError::NotSquare(tiles.len()))?;
+        let mut edges: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (index, tile) in tiles.iter().enumerate() {
+            for &side in SIDES.iter() {
+                edges
+                    .entry(tile.grid.canonical_edge(side))
+                    .or_default()
+                    .push(index);
+            }
+        }
+        if let Some(shared) = edges.values().find(|shared| shared.len() > 2) {
+            return Err(JigsawError::SharedEdge(
+                shared.iter().map(|&i| tiles[i].id).collect(),
+            ));
+        }
+        Ok(Jigsaw {
+            tiles,
+            across,
+            edges,
+        })
+    }
+
+    /// Whether no other tile has this edge of `grid`.
+    fn is_outside(&self, grid: &Grid, side: Side) -> bool {
+        self.edges[&grid.canonical_edge(side)].len() == 1
+    }
+
+    /// The tile other than `index` that has this edge of `grid`.
+    fn neighbour(&self, index: usize, grid: &Grid, side: Side) -> Option<usize> {
+        self.edges[&grid.canonical_edge(side)]
+            .iter()
+            .copied()
+            .find(|&other| other != index)
+    }
+
+    /// The indexes of the corner tiles.
+    fn corners(&self) -> Result<Vec<usize>, JigsawError> {
+        let corners: Vec<usize> = (0..self.tiles.len())
+            .filter(|&index| {
+                let grid = &self.tiles[index].grid;
+                let outside = SIDES
+                    .iter()
+                    .filter(|&&side| self.is_outside(grid, side))
+                    .count();
+                outside >= 2
+            })
+            .collect();
+        // a single tile is all four corners at once
+        let expected = if self.across == 1 { 1 } else { 4 };
+        if corners.len() != expected {
+            return Err(JigsawError::Corners(corners.len()));
+        }
+        Ok(corners)
+    }
+
+    /// Puts the tiles in place and joins them up without their borders.
+    fn assemble(&self) -> Result<Grid, JigsawError> {
+        let corner = self.corners()?[0];
+        let top_left = self.tiles[corner]
+            .orientations()
+            .find(|t| self.is_outside(&t.grid, Side::Top) && self.is_outside(&t.grid, Side::Left))
+            .ok_or(JigsawError::NoFit { row: 0, column: 0 })?;
+
+        let mut used = vec![false; self.tiles.len()];
+        used[corner] = true;
+        // (index, oriented tile) in row order
+        let mut placed: Vec<(usize, Tile)> = vec![(corner, top_left)];
+        for position in 1..self.tiles.len() {
+            let (row, column) = (position / self.across, position % self.across);
+            let no_fit = JigsawError::NoFit { row, column };
+            let above = row
+                .checked_sub(1)
+                .map(|_| &placed[position - self.across].1);
+            // the tile to the left, or the one above at the start of a row
+            let (anchor, anchor_side, side) = if column > 0 {
+                (&placed[position - 1], Side::Right, Side::Left)
+            } else {
+                (&placed[position - self.across], Side::Bottom, Side::Top)
+            };
+            let (anchor_index, anchor_tile) = anchor;
+            let next = self
+                .neighbour(*anchor_index, &anchor_tile.grid, anchor_side)
+                .filter(|&next| !used[next])
+                .ok_or_else(|| no_fit.clone())?;
+            let wanted = anchor_tile.edge(anchor_side);
+            let tile = self.tiles[next]
+                .orientations()
+                .find(|t| {
+                    t.edge(side) == wanted
+                        && above.map_or(true, |above| t.edge(Side::Top) == above.edge(Side::Bottom))
+                })
+                .ok_or(no_fit)?;
+            used[next] = true;
+            placed.push((next, tile));
+        }
+
+        let inners: Vec<Grid> = placed.iter().map(|(_, tile)| tile.grid.inner()).collect();
+        Ok(Grid::join(&inners, self.across))
+    }
+}
+
+/// The (row, column) of every `#` in a sea monster, from its top left corner.
+fn monster() -> Vec<(usize, usize)> {
+    SEA_MONSTER
+        .iter()
+        .enumerate()
+        .flat_map(|(row, line)| {
+            line.char_indices()
+                .filter(|&(_, c)| c == '#')
+                .map(move |(column, _)| (row, column))
+        })
+        .collect()
+}
+
+/// The top left corner of every sea monster in `image` as it is, without turning it. An image
+/// smaller than a sea monster has none.
+pub fn find_monsters(image: &Grid) -> Vec<(usize, usize)> {
+    let monster = monster();
+    let height = SEA_MONSTER.len();
+    let width = SEA_MONSTER[0].len();
+    let mut found = Vec::new();
+    if image.size() < height || image.size() < width {
+        return found;
+    }
+    for row in 0..=image.size() - height {
+        for column in 0..=image.size() - width {
+            if monster.iter().all(|&(r, c)| image.get(row + r, column + c)) {
+                found.push((row, column));
+            }
+        }
+    }
+    found
+}
+
+/// How many `#` aren't part of a sea monster, in the orientation of the image that has some.
+pub fn roughness(image: &Grid) -> Option<usize> {
+    let monster = monster();
+    image.orientations().into_iter().find_map(|image| {
+        let found = find_monsters(&image);
+        if found.is_empty() {
+            return None;
+        }
+        // monsters can overlap, so count each pixel once
+        let covered: HashSet<(usize, usize)> = found
+            .iter()
+            .flat_map(|&(row, column)| monster.iter().map(move |&(r, c)| (row + r, column + c)))
+            .collect();
+        Some(image.count() - covered.len())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Tile 2311:
+        ..##.#..#.
+        ##..#.....
+        #...##..#.
+        ####.#...#
+        ##.##.###.
+        ##...#.###
+        .#.#.#..##
+        ..#....#..
+        ###...#.#.
+        ..###..###
+
+        Tile 1951:
+        #.##...##.
+        #.####...#
+        .....#..##
+        #...######
+        .##.#....#
+        .###.#####
+        ###.##.##.
+        .###....#.
+        ..#.#..#.#
+        #...##.#..
+
+        Tile 1171:
+        ####...##.
+        #..##.#..#
+        ##.#..#.#.
+        .###.####.
+        ..###.####
+        .##....##.
+        .#...####.
+        #.##.####.
+        ####..#...
+        .....##...
+
+        Tile 1427:
+        ###.##.#..
+        .#..#.##..
+        .#.##.#..#
+        #.#.#.##.#
+        ....#...##
+        ...##..##.
+        ...#.#####
+        .#.####.#.
+        ..#..###.#
+        ..##.#..#.
+
+        Tile 1489:
+        ##.#.#....
+        ..##...#..
+        .##..##...
+        ..#...#...
+        #####...#.
+        #..#.#.#.#
+        ...#.#.#..
+        ##.#...##.
+        ..##.##.##
+        ###.##.#..
+
+        Tile 2473:
+        #....####.
+        #..#.##...
+        #.##..#...
+        ######.#.#
+        .#...#.#.#
+        .#########
+        .###.#..#.
+        ########.#
+        ##...##.#.
+        ..###.#.#.
+
+        Tile 2971:
+        ..#.#....#
+        #...###...
+        #.#.###...
+        ##.##..#..
+        .#####..##
+        .#..####.#
+        #..#.#..#.
+        ..####.###
+        ..#.#.###.
+        ...#.#.#.#
+
+        Tile 2729:
+        ...#.#.#.#
+        ####.#....
+        ..#.#.....
+        ....#..#.#
+        .##..##.#.
+        .#.####...
+        ####.#.#..
+        ##.####...
+        ##..#.##..
+        #.##...##.
+
+        Tile 3079:
+        #.#.#####.
+        .#..######
+        ..#.......
+        ######....
+        ####.#..#.
+        .#...#.##.
+        #.#####.##
+        ..#.###...
+        ..#.......
+        ..#.###...";
+
+    #[test]
+    fn it_should_find_the_corners() {
+        let tiles = parse_tiles(EXAMPLE).unwrap();
+        let jigsaw = Jigsaw::new(&tiles).unwrap();
+        let mut corners: Vec<u64> = jigsaw
+            .corners()
+            .unwrap()
+            .into_iter()
+            .map(|i| tiles[i].id)
+            .collect();
+        corners.sort_unstable();
+        assert_eq!(corners, vec![1171, 1951, 2971, 3079]);
+        assert_eq!(day20_1(EXAMPLE), Ok(20899048083289));
+    }
+
+    #[test]
+    fn it_should_assemble_the_image() {
+        let tiles = parse_tiles(EXAMPLE).unwrap();
+        let image = Jigsaw::new(&tiles).unwrap().assemble().unwrap();
+        assert_eq!(image.size(), 24);
+        // the example image, however it ended up turned
+        let expected = [
+            ".#.#..#.##...#.##..#####",
+            "###....#.#....#..#......",
+            "##.##.###.#.#..######...",
+            "###.#####...#.#####.#..#",
+            "##.#....#.##.####...#.##",
+            "...########.#....#####.#",
+            "....#..#...##..#.#.###..",
+            ".####...#..#.....#......",
+            "#..#.##..#..###.#.##....",
+            "#.####..#.####.#.#.###..",
+            "###.#.#...#.######.#..##",
+            "#.####....##..########.#",
+            "##..##.#...#...#.#.#.#..",
+            "...#..#..#.#.##..###.###",
+            ".#.#....#.##.#...###.##.",
+            "###.#...#..#.##.######..",
+            ".#.#.###.##.##.#..#.##..",
+            ".####.###.#...###.#..#.#",
+            "..#.#..#..#.#.#.####.###",
+            "#..####...#.#.#.###.###.",
+            "#####..#####...###....##",
+            "#.##..#..#...#..####...#",
+            ".#.###..##..##..####.##.",
+            "...###...##...#...#..###",
+        ];
+        let expected = Grid::parse(&expected).unwrap();
+        assert!(expected.orientations().contains(&image));
+    }
+
+    #[test]
+    fn it_should_find_the_monsters() {
+        let tiles = parse_tiles(EXAMPLE).unwrap();
+        let image = Jigsaw::new(&tiles).unwrap().assemble().unwrap();
+        let monsters: Vec<usize> = image
+            .orientations()
+            .iter()
+            .map(|image| find_monsters(image).len())
+            .collect();
+        assert_eq!(monsters.iter().filter(|&&n| n > 0).count(), 1);
+        assert_eq!(monsters.iter().sum::<usize>(), 2);
+        assert_eq!(day20_2(EXAMPLE), Ok(273));
+    }
+
+    #[test]
+    fn it_should_not_look_for_monsters_past_a_small_image() {
+        let full = ["########"; 8];
+        assert!(find_monsters(&Grid::parse(&full).unwrap()).is_empty());
+        // a single tile is an 8x8 image once its borders are gone
+        let tile = "Tile 1:
+##........
+..........
+.........#
+.........#
+.........#
+#........#
+.........#
+.........#
+..........
+....###...";
+        assert_eq!(day20_2(tile), Err(JigsawError::NoMonsters));
+    }
+
+    #[test]
+    fn it_should_not_overflow_the_corner_product() {
+        // 1951 is a corner
+        let input = EXAMPLE.replace("Tile 1951:", "Tile 18446744073709551615:");
+        assert_eq!(day20_1(&input), Err(JigsawError::Overflow));
+    }
+
+    #[test]
+    fn it_should_report_bad_tiles() {
+        let error = |input| day20_1(input).unwrap_err().to_string();
+        assert_eq!(
+            error("Tile x:\n#.\n.#"),
+            "line 1: invalid tile header: Tile x:"
+        );
+        assert_eq!(
+            error("#.\n.#"),
+            "line 1: pixels before the first tile header"
+        );
+        assert_eq!(
+            error("Tile 1:\n#.\n.#\n\nTile 2:\n#.#\n.#."),
+            "line 5: tile 2: expected 2 pixels in every row, found: #.#"
+        );
+        assert_eq!(
+            error("Tile 1:\n#.\n.#\n\nTile 2:\n#.\n.#"),
+            "2 tiles can't make a square"
+        );
+    }
+}
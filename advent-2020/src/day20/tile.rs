@@ -0,0 +1,227 @@
+/*
+Square grids of pixels. A tile can be rotated and flipped, which makes 8 ways to lay it down: the
+4 rotations of the tile and the 4 rotations of its mirror image. The finished image is a Grid too,
+so the monster search turns it the same way.
+
+An edge is read left to right along the top and bottom and top to bottom down the sides, packed
+into the bits of a u32 (so tiles are at most 32 pixels across). Two edges line up when they have
+the same bits. A flip reverses some of them, so edges are compared by their canonical form, the
+smaller of the bits and the bits reversed, when looking for tiles that might go together.
+*/
+use std::fmt::{Display, Formatter};
+
+pub const MAX_TILE_SIZE: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+pub const SIDES: [Side; 4] = [Side::Top, Side::Right, Side::Bottom, Side::Left];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    size: usize,
+    /// Row by row, true is `#`
+    pixels: Vec<bool>,
+}
+
+impl Grid {
+    /// `#` and `.` rows, as many as there are columns, and at least one.
+    pub fn parse(rows: &[&str]) -> Result<Grid, String> {
+        let size = rows.len();
+        if size == 0 {
+            return Err("a grid needs at least one row".to_string());
+        }
+        let mut pixels = Vec::with_capacity(size * size);
+        for row in rows {
+            if row.chars().count() != size {
+                return Err(format!(
+                    "expected {} pixels in every row, found: {}",
+                    size, row
+                ));
+            }
+            for c in row.chars() {
+                pixels.push(match c {
+                    '#' => true,
+                    '.' => false,
+                    _ => return Err(format!("expected '#' or '.', found '{}'", c)),
+                });
+            }
+        }
+        Ok(Grid { size, pixels })
+    }
+
+    fn from_fn<F: Fn(usize, usize) -> bool>(size: usize, pixel: F) -> Grid {
+        let pixels = (0..size * size)
+            .map(|index| pixel(index / size, index % size))
+            .collect();
+        Grid { size, pixels }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> bool {
+        self.pixels[row * self.size + column]
+    }
+
+    /// How many pixels are `#`.
+    pub fn count(&self) -> usize {
+        self.pixels.iter().filter(|&&pixel| pixel).count()
+    }
+
+    /// A quarter turn clockwise.
+    pub fn rotate(&self) -> Grid {
+        // an empty grid (what's inside a 2 pixel tile) has no pixels to look up
+        let last = self.size.saturating_sub(1);
+        Grid::from_fn(self.size, |row, column| self.get(last - column, row))
+    }
+
+    /// Mirrored left to right.
+    pub fn flip(&self) -> Grid {
+        let last = self.size.saturating_sub(1);
+        Grid::from_fn(self.size, |row, column| self.get(row, last - column))
+    }
+
+    /// The 4 rotations, then the 4 rotations of the mirror image.
+    pub fn orientations(&self) -> Vec<Grid> {
+        let mut orientations = Vec::with_capacity(8);
+        for start in [self.clone(), self.flip()].iter() {
+            let mut grid = start.clone();
+            for _ in 0..4 {
+                let next = grid.rotate();
+                orientations.push(grid);
+                grid = next;
+            }
+        }
+        orientations
+    }
+
+    /// The grid without its outermost ring of pixels.
+    pub fn inner(&self) -> Grid {
+        let size = self.size.saturating_sub(2);
+        Grid::from_fn(size, |row, column| self.get(row + 1, column + 1))
+    }
+
+    /// Lays `tiles` (`across` of them to a row) out next to each other as one grid.
+    pub fn join(tiles: &[Grid], across: usize) -> Grid {
+        let tile_size = tiles.first().map_or(0, Grid::size);
+        Grid::from_fn(across * tile_size, |row, column| {
+            tiles[row / tile_size * across + column / tile_size]
+                .get(row % tile_size, column % tile_size)
+        })
+    }
+
+    pub fn edge(&self, side: Side) -> u32 {
+        let last = self.size - 1;
+        (0..self.size)
+            .map(|i| match side {
+                Side::Top => self.get(0, i),
+                Side::Bottom => self.get(last, i),
+                Side::Left => self.get(i, 0),
+                Side::Right => self.get(i, last),
+            })
+            .fold(0, |edge, pixel| edge << 1 | u32::from(pixel))
+    }
+
+    /// The same edge whichever way round it's read.
+    pub fn canonical_edge(&self, side: Side) -> u32 {
+        let edge = self.edge(side);
+        let reversed = edge.reverse_bits() >> (32 - self.size);
+        edge.min(reversed)
+    }
+}
+
+impl Display for Grid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for row in self.pixels.chunks(self.size.max(1)) {
+            let line: String = row.iter().map(|&p| if p { '#' } else { '.' }).collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tile {
+    pub id: u64,
+    pub grid: Grid,
+}
+
+impl Tile {
+    pub fn orientations(&self) -> impl Iterator<Item = Tile> + '_ {
+        self.grid
+            .orientations()
+            .into_iter()
+            .map(move |grid| Tile { id: self.id, grid })
+    }
+
+    pub fn edge(&self, side: Side) -> u32 {
+        self.grid.edge(side)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &str) -> Grid {
+        let rows: Vec<&str> = rows.lines().map(str::trim).collect();
+        Grid::parse(&rows).unwrap()
+    }
+
+    #[test]
+    fn it_should_turn_and_flip() {
+        let g = grid("#..\n##.\n...");
+        assert_eq!(g.rotate().to_string(), ".##\n.#.\n...\n");
+        assert_eq!(g.flip().to_string(), "..#\n.##\n...\n");
+        assert_eq!(g.rotate().rotate().rotate().rotate(), g);
+        assert_eq!(g.flip().flip(), g);
+        let orientations = g.orientations();
+        assert_eq!(orientations.len(), 8);
+        assert!(orientations
+            .iter()
+            .enumerate()
+            .all(|(i, a)| orientations[i + 1..].iter().all(|b| a != b)));
+    }
+
+    #[test]
+    fn it_should_read_edges() {
+        let g = grid("#..\n##.\n..#");
+        assert_eq!(g.edge(Side::Top), 0b100);
+        assert_eq!(g.edge(Side::Left), 0b110);
+        assert_eq!(g.edge(Side::Right), 0b001);
+        assert_eq!(g.edge(Side::Bottom), 0b001);
+        assert_eq!(g.canonical_edge(Side::Top), 0b001);
+        // a flip reverses the top edge, but it's still the same edge
+        assert_eq!(g.flip().canonical_edge(Side::Top), 0b001);
+    }
+
+    #[test]
+    fn it_should_strip_and_join() {
+        let g = grid("....\n.#..\n..#.\n....");
+        assert_eq!(g.inner().to_string(), "#.\n.#\n");
+        let (a, b) = (g.inner(), g.inner().flip());
+        let joined = Grid::join(&[a.clone(), b.clone(), b, a], 2);
+        assert_eq!(joined.size(), 4);
+        assert_eq!(joined.to_string(), "#..#\n.##.\n.##.\n#..#\n");
+        // nothing's left inside a 2 pixel grid, and nothing to turn
+        let empty = grid("#.\n.#").inner();
+        assert_eq!(empty.size(), 0);
+        assert_eq!(empty.rotate(), empty);
+        assert_eq!(empty.flip(), empty);
+    }
+
+    #[test]
+    fn it_should_reject_empty_grids() {
+        assert_eq!(
+            Grid::parse(&[]),
+            Err("a grid needs at least one row".to_string())
+        );
+    }
+}
@@ -3,3 +3,8 @@ use std::fs;
 pub fn fetch_input_file(file_path: &str) -> String {
     fs::read_to_string(file_path).expect("Input File not found??")
 }
+
+/// For days whose puzzle input isn't checked in.
+pub fn fetch_optional_input_file(file_path: &str) -> Option<String> {
+    fs::read_to_string(file_path).ok()
+}
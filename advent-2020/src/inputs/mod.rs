@@ -1,2 +1,2 @@
 mod fetch_input;
-pub use fetch_input::fetch_input_file;
+pub use fetch_input::{fetch_input_file, fetch_optional_input_file};
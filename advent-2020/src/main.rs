@@ -8,14 +8,70 @@ mod day05;
 mod day06;
 mod day07;
 mod day08;
+mod day09;
+mod day10;
+mod day11;
+mod day12;
+mod day13;
+mod day14;
+mod day15;
+mod day16;
+mod day17;
+mod day18;
+mod day19;
+mod day20;
 mod inputs;
 mod parsers;
 #[macro_use]
 extern crate pest_derive;
 
-use inputs::fetch_input_file;
+use inputs::{fetch_input_file, fetch_optional_input_file};
+use std::fmt::Debug;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [] => print_all_solutions(),
+        ["day03", "path", right, down] => match (right.parse(), down.parse()) {
+            (Ok(right), Ok(down)) if down > 0 => print!("{}", do_day03_path(right, down)),
+            _ => usage(),
+        },
+        ["day03", "best", max_right, max_down] => match (max_right.parse(), max_down.parse()) {
+            (Ok(max_right), Ok(max_down)) => println!("{}", do_day03_best(max_right, max_down)),
+            _ => usage(),
+        },
+        ["day05", "seats"] => print!("{}", do_day05_seat_map()),
+        ["day06", "histogram"] => print!("{}", do_day06_histogram()),
+        ["day07", "check"] => println!("{}", do_day07_check()),
+        ["day07", "dot"] => print!("{}", do_day07_dot(day07::DotScope::Everything)),
+        ["day07", "dot", "--from", color] => {
+            print!("{}", do_day07_dot(day07::DotScope::From(color)))
+        }
+        ["day07", "dot", "--into", color] => {
+            print!("{}", do_day07_dot(day07::DotScope::Into(color)))
+        }
+        ["day08", "trace"] => print!("{}", do_day08_trace()),
+        ["day08", "debug"] => do_day08_debug(),
+        ["day08", "repair"] => println!("{}", do_day08_repair()),
+        ["day08", "asm", path] => print!("{}", do_day08_assemble(path)),
+        ["day08", "disasm"] => print!("{}", do_day08_disassemble()),
+        ["day11", "generations"] => {
+            print_output_if_input("11", do_day11_generations(day11::Rule::Adjacent))
+        }
+        ["day11", "generations", "--sight"] => {
+            print_output_if_input("11", do_day11_generations(day11::Rule::LineOfSight))
+        }
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: advent-2020 [day03 path RIGHT DOWN | day03 best MAX_RIGHT MAX_DOWN | day05 seats | day06 histogram | day07 check | day07 dot [--from COLOR | --into COLOR] | day08 trace | day08 debug | day08 repair | day08 asm FILE | day08 disasm | day11 generations [--sight]]");
+    std::process::exit(1);
+}
+
+fn print_all_solutions() {
     println!("Solution for Day 01 Pt. 1 is {}", do_day01_1());
     println!("Solution for Day 01 Pt. 2 is {}", do_day01_2());
     println!("Solution for Day 02 Pt. 1 is {}", do_day02_1());
@@ -26,6 +82,40 @@ fn main() {
     println!("Solutions for Day 06: (Pt. 1, Pt. 2) is {:?}", do_day06());
     println!("Solutions for Day 07: (Pt. 1, Pt. 2) is {:?}", do_day07());
     println!("Solutions for Day 08: (Pt. 1, Pt. 2) is {:?}", do_day08());
+    print_if_input("09", do_day09());
+    print_if_input("10", do_day10());
+    print_if_input("11", do_day11());
+    print_if_input("12", do_day12());
+    print_if_input("13", do_day13());
+    print_if_input("14", do_day14());
+    print_if_input("15", do_day15());
+    print_if_input("16", do_day16());
+    print_if_input("17", do_day17());
+    print_if_input("18", do_day18());
+    print_if_input("19", do_day19());
+    print_if_input("20", do_day20());
+}
+
+/// Later days don't have their input checked in, so they're skipped when it's missing.
+fn print_if_input<T: Debug>(day: &str, solutions: Option<T>) {
+    match solutions {
+        Some(solutions) => println!(
+            "Solutions for Day {}: (Pt. 1, Pt. 2) is {:?}",
+            day, solutions
+        ),
+        None => println!(
+            "Solutions for Day {}: no input at src/inputs/day{}",
+            day, day
+        ),
+    }
+}
+
+/// Like `print_if_input`, for commands that print more than the two solutions.
+fn print_output_if_input(day: &str, output: Option<String>) {
+    match output {
+        Some(output) => print!("{}", output),
+        None => println!("Day {}: no input at src/inputs/day{}", day, day),
+    }
 }
 
 fn do_day01_1() -> i32 {
@@ -53,11 +143,33 @@ fn do_day03() -> (usize, usize) {
     let pt1 = day03_1_function(
         &fetch_input_file("src/inputs/day03"),
         Slope { right: 3, down: 1 },
-    );
-    let pt2 = day03_2_function(&fetch_input_file("src/inputs/day03"), get_slopes());
+    )
+    .expect("day03 map is broken");
+    let pt2 = day03_2_function(&fetch_input_file("src/inputs/day03"), get_slopes())
+        .expect("day03 map is broken");
     (pt1, pt2)
 }
 
+fn do_day03_path(right: usize, down: usize) -> String {
+    use day03::*;
+    match day03_path(&fetch_input_file("src/inputs/day03"), Slope { right, down }) {
+        Ok(map) => map,
+        Err(e) => format!("{}\n", e),
+    }
+}
+
+fn do_day03_best(max_right: usize, max_down: usize) -> String {
+    use day03::*;
+    match day03_best_slope(&fetch_input_file("src/inputs/day03"), max_right, max_down) {
+        Ok(Some((slope, trees))) => format!(
+            "right {}, down {} hits {} trees",
+            slope.right, slope.down, trees
+        ),
+        Ok(None) => "no slopes to try, MAX_RIGHT and MAX_DOWN have to be at least 1".to_string(),
+        Err(e) => e.to_string(),
+    }
+}
+
 fn do_day04() -> (usize, usize) {
     use day04::*;
     let pt1 = day04_1(&fetch_input_file("src/inputs/day04"));
@@ -72,23 +184,179 @@ fn do_day05() -> (u32, u32) {
     (pt1, pt2)
 }
 
+fn do_day05_seat_map() -> String {
+    use day05::*;
+    day05_seat_map(&fetch_input_file("src/inputs/day05"))
+}
+
 fn do_day06() -> (usize, usize) {
     use day06::*;
-    let pt1 = day06_1(&fetch_input_file("src/inputs/day06"));
-    let pt2 = day06_2(&fetch_input_file("src/inputs/day06"));
+    let pt1 = day06_1(&fetch_input_file("src/inputs/day06")).expect("day06 answers are broken");
+    let pt2 = day06_2(&fetch_input_file("src/inputs/day06")).expect("day06 answers are broken");
     (pt1, pt2)
 }
 
-fn do_day07() -> (usize, i32) {
+fn do_day06_histogram() -> String {
+    use day06::*;
+    day06_histogram(&fetch_input_file("src/inputs/day06")).expect("day06 answers are broken")
+}
+
+fn do_day07() -> (usize, u64) {
     use day07::*;
-    let pt1 = day07_1(&fetch_input_file("src/inputs/day07"));
-    let pt2 = day07_2(&fetch_input_file("src/inputs/day07"));
+    let pt1 = day07_1(&fetch_input_file("src/inputs/day07")).expect("day07 bag rules are broken");
+    let pt2 = day07_2(&fetch_input_file("src/inputs/day07")).expect("day07 bag rules are broken");
     (pt1, pt2)
 }
 
-fn do_day08() -> (i32, i32) {
+fn do_day07_check() -> String {
+    use day07::*;
+    day07_check(&fetch_input_file("src/inputs/day07"))
+}
+
+fn do_day07_dot(scope: day07::DotScope) -> String {
+    use day07::*;
+    day07_dot(&fetch_input_file("src/inputs/day07"), scope).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+fn do_day08() -> (i64, i64) {
     use day08::*;
-    let pt1 = day08_1(&fetch_input_file("src/inputs/day08"));
-    let pt2 = day08_2(&fetch_input_file("src/inputs/day08"));
+    let pt1 = day08_1(&fetch_input_file("src/inputs/day08")).expect("day08 program is broken");
+    let pt2 = day08_2(&fetch_input_file("src/inputs/day08")).expect("day08 program is broken");
     (pt1, pt2)
 }
+
+fn do_day08_trace() -> String {
+    use day08::*;
+    day08_trace(&fetch_input_file("src/inputs/day08")).expect("day08 program is broken")
+}
+
+fn do_day08_repair() -> String {
+    use day08::*;
+    day08_repair(&fetch_input_file("src/inputs/day08")).expect("day08 program is broken")
+}
+
+fn do_day08_assemble(path: &str) -> String {
+    use day08::*;
+    day08_assemble(&fetch_input_file(path)).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        std::process::exit(1);
+    })
+}
+
+fn do_day08_disassemble() -> String {
+    use day08::*;
+    day08_disassemble(&fetch_input_file("src/inputs/day08")).expect("day08 program is broken")
+}
+
+fn do_day08_debug() {
+    use day08::*;
+    if let Err(e) = day08_debug(&fetch_input_file("src/inputs/day08")) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn do_day09() -> Option<(u64, u64)> {
+    use day09::*;
+    let input = fetch_optional_input_file("src/inputs/day09")?;
+    let pt1 = day09_1(&input, PREAMBLE).expect("day09 has no invalid number");
+    let pt2 = day09_2(&input, PREAMBLE).expect("day09 has no encryption weakness");
+    Some((pt1, pt2))
+}
+
+fn do_day10() -> Option<(usize, u128)> {
+    use day10::*;
+    let input = fetch_optional_input_file("src/inputs/day10")?;
+    let pt1 = day10_1(&input).expect("day10 adapters don't chain");
+    let pt2 = day10_2(&input).expect("day10 adapters don't chain");
+    Some((pt1, pt2))
+}
+
+fn do_day11() -> Option<(usize, usize)> {
+    use day11::*;
+    let input = fetch_optional_input_file("src/inputs/day11")?;
+    let pt1 = day11_1(&input).expect("day11 seat layout is broken");
+    let pt2 = day11_2(&input).expect("day11 seat layout is broken");
+    Some((pt1, pt2))
+}
+
+fn do_day11_generations(rule: day11::Rule) -> Option<String> {
+    use day11::*;
+    let input = fetch_optional_input_file("src/inputs/day11")?;
+    Some(day11_generations(&input, rule).expect("day11 seat layout is broken"))
+}
+
+fn do_day12() -> Option<(i32, i32)> {
+    use day12::*;
+    let input = fetch_optional_input_file("src/inputs/day12")?;
+    let pt1 = day12_1(&input).expect("day12 navigation instructions are broken");
+    let pt2 = day12_2(&input).expect("day12 navigation instructions are broken");
+    Some((pt1, pt2))
+}
+
+fn do_day13() -> Option<(u64, u128)> {
+    use day13::*;
+    let input = fetch_optional_input_file("src/inputs/day13")?;
+    let pt1 = day13_1(&input).expect("day13 bus notes are broken");
+    let pt2 = day13_2(&input).expect("day13 bus notes are broken");
+    Some((pt1, pt2))
+}
+
+fn do_day14() -> Option<(u64, u64)> {
+    use day14::*;
+    let input = fetch_optional_input_file("src/inputs/day14")?;
+    let pt1 = day14_1(&input).expect("day14 docking program is broken");
+    let pt2 = day14_2(&input).expect("day14 docking program is broken");
+    Some((pt1, pt2))
+}
+
+fn do_day15() -> Option<(u32, u32)> {
+    use day15::*;
+    let input = fetch_optional_input_file("src/inputs/day15")?;
+    let pt1 = day15_1(&input).expect("day15 starting numbers are broken");
+    let pt2 = day15_2(&input).expect("day15 starting numbers are broken");
+    Some((pt1, pt2))
+}
+
+fn do_day16() -> Option<(u32, u64)> {
+    use day16::*;
+    let input = fetch_optional_input_file("src/inputs/day16")?;
+    let pt1 = day16_1(&input).expect("day16 ticket notes are broken");
+    let pt2 = day16_2(&input).expect("day16 ticket notes are broken");
+    Some((pt1, pt2))
+}
+
+fn do_day17() -> Option<(usize, usize)> {
+    use day17::*;
+    let input = fetch_optional_input_file("src/inputs/day17")?;
+    let pt1 = day17_1(&input).expect("day17 starting slice is broken");
+    let pt2 = day17_2(&input).expect("day17 starting slice is broken");
+    Some((pt1, pt2))
+}
+
+fn do_day18() -> Option<(u64, u64)> {
+    use day18::*;
+    let input = fetch_optional_input_file("src/inputs/day18")?;
+    let pt1 = day18_1(&input).expect("day18 homework is broken");
+    let pt2 = day18_2(&input).expect("day18 homework is broken");
+    Some((pt1, pt2))
+}
+
+fn do_day19() -> Option<(usize, usize)> {
+    use day19::*;
+    let input = fetch_optional_input_file("src/inputs/day19")?;
+    let pt1 = day19_1(&input).expect("day19 rules are broken");
+    let pt2 = day19_2(&input).expect("day19 rules are broken");
+    Some((pt1, pt2))
+}
+
+fn do_day20() -> Option<(u64, usize)> {
+    use day20::*;
+    let input = fetch_optional_input_file("src/inputs/day20")?;
+    let pt1 = day20_1(&input).expect("day20 tiles are broken");
+    let pt2 = day20_2(&input).expect("day20 tiles are broken");
+    Some((pt1, pt2))
+}
@@ -167,10 +167,7 @@ impl Passport {
     }
 
     fn has_valid_hcl(&self) -> bool {
-        match parse_hcl(self.hcl.as_ref().unwrap()) {
-            None => false,
-            Some(_) => true,
-        }
+        parse_hcl(self.hcl.as_ref().unwrap()).is_some()
     }
 
     fn has_valid_pid(&self) -> bool {
@@ -189,10 +186,7 @@ impl Passport {
     }
 
     fn parse_string(m: &HashMap<&str, &str>, k: &str) -> Option<String> {
-        match m.get(k) {
-            None => None,
-            Some(&v) => Some(String::from(v)),
-        }
+        m.get(k).map(|&v| String::from(v))
     }
 
     fn parse_eye_color(m: &HashMap<&str, &str>, k: &str) -> Option<EyeColor> {
@@ -205,10 +199,7 @@ impl Passport {
     fn parse_height(m: &HashMap<&str, &str>, k: &str) -> Option<Hgt> {
         match m.get(k) {
             None => None,
-            Some(&v) => match parser_hgt(v).ok() {
-                None => None,
-                Some((_, hgt)) => Some(hgt),
-            },
+            Some(&v) => parser_hgt(v).ok().map(|(_, hgt)| hgt),
         }
     }
 }
@@ -217,13 +208,13 @@ impl Passport {
 struct Hgt(u8, String);
 impl Hgt {
     fn is_valid(&self) -> bool {
-        return if self.1.eq_ignore_ascii_case("cm") {
+        if self.1.eq_ignore_ascii_case("cm") {
             self.0 >= 150 && self.0 <= 193
         } else if self.1.eq_ignore_ascii_case("in") {
             self.0 >= 59 && self.0 <= 76
         } else {
             false
-        };
+        }
     }
 }
 
@@ -325,7 +316,7 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
             pid: None,
             cid: Some(String::from("100")),
         };
-        assert_eq!(expected.is_valid(), false);
+        assert!(!expected.is_valid());
     }
 
     #[test]
@@ -344,7 +335,7 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
             pid: Some(String::from("087499704")),
             cid: Some(String::from("100")),
         };
-        assert_eq!(expected.is_valid(), false);
+        assert!(!expected.is_valid());
     }
 
     #[test]
@@ -363,7 +354,7 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
             pid: Some(String::from("087499704")),
             cid: Some(String::from("100")),
         };
-        assert_eq!(expected.is_valid(), false);
+        assert!(!expected.is_valid());
     }
 
     #[test]
@@ -382,7 +373,7 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
             pid: Some(String::from("087499704")),
             cid: Some(String::from("100")),
         };
-        assert_eq!(expected.is_valid(), false);
+        assert!(!expected.is_valid());
     }
 
     #[test]
@@ -401,7 +392,7 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
             pid: Some(String::from("087499704")),
             cid: Some(String::from("100")),
         };
-        assert_eq!(expected.is_valid(), false);
+        assert!(!expected.is_valid());
     }
 
     #[test]
@@ -420,7 +411,7 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
             pid: Some(String::from("087499704")),
             cid: Some(String::from("100")),
         };
-        assert_eq!(expected.is_valid(), false);
+        assert!(!expected.is_valid());
     }
 
     #[test]
@@ -439,7 +430,7 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
             pid: Some(String::from("087499704")),
             cid: Some(String::from("100")),
         };
-        assert_eq!(expected.is_valid(), false);
+        assert!(!expected.is_valid());
     }
 
     #[test]
@@ -458,7 +449,7 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
             pid: Some(String::from("0123456789")),
             cid: Some(String::from("100")),
         };
-        assert_eq!(expected.is_valid(), false);
+        assert!(!expected.is_valid());
     }
 
     #[test]
@@ -518,7 +509,7 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
             parser_hgt(invalid_snippet),
             Ok(("", Hgt(123, "in".to_owned())))
         );
-        assert_eq!(parser_hgt(valid_snippet).unwrap().1.is_valid(), true);
+        assert!(parser_hgt(valid_snippet).unwrap().1.is_valid());
     }
 
     #[test]
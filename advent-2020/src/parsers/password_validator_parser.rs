@@ -58,8 +58,7 @@ pub struct PasswordValidator {
 impl PasswordValidator {
     pub fn is_valid(&self) -> bool {
         let occurrences = self.password.matches(&self.pattern).count();
-        occurrences.ge(&usize::from(self.min_occurrence))
-            && occurrences.le(&usize::from(self.max_occurrence))
+        occurrences.ge(&self.min_occurrence) && occurrences.le(&self.max_occurrence)
     }
 
     pub fn is_positionally_valid(&self) -> bool {
@@ -67,8 +66,8 @@ impl PasswordValidator {
             == self.pattern.parse::<char>().unwrap()
             || self.password.chars().nth(self.max_occurrence - 1).unwrap()
                 == self.pattern.parse::<char>().unwrap())
-            && !(self.password.chars().nth(self.min_occurrence - 1).unwrap()
-                == self.password.chars().nth(self.max_occurrence - 1).unwrap())
+            && self.password.chars().nth(self.min_occurrence - 1).unwrap()
+                != self.password.chars().nth(self.max_occurrence - 1).unwrap()
     }
 }
 
@@ -100,7 +99,7 @@ mod tests {
             pattern: String::from("a"),
             password: String::from("abcde"),
         };
-        assert_eq!(pv.is_valid(), true);
+        assert!(pv.is_valid());
     }
 
     #[test]
@@ -111,7 +110,7 @@ mod tests {
             pattern: String::from("a"),
             password: String::from("abcde"),
         };
-        assert_eq!(pv.is_positionally_valid(), true);
+        assert!(pv.is_positionally_valid());
     }
 
     #[test]
@@ -122,7 +121,7 @@ mod tests {
             pattern: String::from("b"),
             password: String::from("cdefg"),
         };
-        assert_eq!(pv.is_positionally_valid(), false);
+        assert!(!pv.is_positionally_valid());
     }
 
     #[test]
@@ -133,7 +132,7 @@ mod tests {
             pattern: String::from("c"),
             password: String::from("ccccccccc"),
         };
-        assert_eq!(pv.is_positionally_valid(), false);
+        assert!(!pv.is_positionally_valid());
     }
 
     #[test]